@@ -0,0 +1,237 @@
+//! `#[derive(GateLayout)]` generates the wire index functions of a gate from a per-op struct.
+//!
+//! ```ignore
+//! #[derive(GateLayout)]
+//! #[gate_layout(gate = SimpleMulAddTestGate, num_ops = num_ops)]
+//! pub struct SimpleMulAddTestLayout {
+//!     pub multiplicand_0: Wire,
+//!     pub multiplicand_1: Wire,
+//!     pub output: Wire,
+//! }
+//! ```
+//!
+//! - `gate` is the gate the functions are generated on.
+//! - `num_ops` is the field of the gate holding the number of repeated ops in a row.
+//!   Without it, a row holds a single op.
+//! - A `Wire` field takes one wire of the op, and generates `wire_ith_<field>(i)`.
+//! - A `Wires` field takes `#[gate_layout(len = <gate field>)]` consecutive wires, and generates
+//!   `wire_ith_<field>(i, j)`. The functions of a layout with `Wires` fields take `&self`.
+//! - A `Constant` field takes one gate constant, and generates `const_<field>()`.
+//!
+//! On top of these, `wires_per_op()`, `num_ops(config)`, `num_wires()` and `layout_num_constants()` are generated.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, Data, DeriveInput, Fields, Ident, Path, Type};
+
+enum FieldKind {
+    Wire,
+    Wires(Ident),
+    Constant,
+}
+
+#[proc_macro_derive(GateLayout, attributes(gate_layout))]
+pub fn derive_gate_layout(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let mut gate: Option<Path> = None;
+    let mut num_ops: Option<Ident> = None;
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("gate_layout")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("gate") {
+                gate = Some(meta.value()?.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("num_ops") {
+                num_ops = Some(meta.value()?.parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("expected `gate` or `num_ops`"))
+            }
+        })?;
+    }
+    let gate = gate.ok_or_else(|| {
+        syn::Error::new_spanned(&input.ident, "missing `#[gate_layout(gate = ...)]`")
+    })?;
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => fields.named.iter().collect::<Vec<_>>(),
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "`GateLayout` needs named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "`GateLayout` can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut layout = Vec::with_capacity(fields.len());
+    for field in fields {
+        let name = field.ident.clone().expect("named field");
+        let type_name = match &field.ty {
+            Type::Path(p) => p.path.segments.last().map(|s| s.ident.to_string()),
+            _ => None,
+        };
+
+        let mut len: Option<Ident> = None;
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("gate_layout")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("len") {
+                    len = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `len`"))
+                }
+            })?;
+        }
+
+        let kind = match (type_name.as_deref(), len) {
+            (Some("Wire"), None) => FieldKind::Wire,
+            (Some("Wires"), Some(len)) => FieldKind::Wires(len),
+            (Some("Wires"), None) => {
+                return Err(syn::Error::new_spanned(
+                    field,
+                    "`Wires` needs `#[gate_layout(len = <gate field>)]`",
+                ))
+            }
+            (Some("Constant"), None) => FieldKind::Constant,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    field,
+                    "expected a `Wire`, `Wires` or `Constant` field",
+                ))
+            }
+        };
+        layout.push((name, kind));
+    }
+
+    let is_static = !layout.iter().any(|(_, k)| matches!(k, FieldKind::Wires(_)));
+    if !is_static && num_ops.is_some() {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "`num_ops` cannot be used together with `Wires` fields",
+        ));
+    }
+
+    // The offset of every wire field in an op, as an expression in the gate's fields.
+    let mut functions = Vec::new();
+    let mut offset = quote!(0usize);
+    let mut num_constants = 0usize;
+    for (name, kind) in layout.iter() {
+        match kind {
+            FieldKind::Wire => {
+                let fn_name = format_ident!("wire_ith_{}", name);
+                functions.push(if is_static {
+                    quote! {
+                        pub fn #fn_name(i: usize) -> usize {
+                            Self::wires_per_op() * i + #offset
+                        }
+                    }
+                } else {
+                    quote! {
+                        pub fn #fn_name(&self, i: usize) -> usize {
+                            self.wires_per_op() * i + #offset
+                        }
+                    }
+                });
+                offset = quote!(#offset + 1);
+            }
+            FieldKind::Wires(len) => {
+                let fn_name = format_ident!("wire_ith_{}", name);
+                functions.push(quote! {
+                    pub fn #fn_name(&self, i: usize, j: usize) -> usize {
+                        debug_assert!(j < self.#len);
+                        self.wires_per_op() * i + #offset + j
+                    }
+                });
+                offset = quote!(#offset + self.#len);
+            }
+            FieldKind::Constant => {
+                let fn_name = format_ident!("const_{}", name);
+                let index = num_constants;
+                functions.push(quote! {
+                    pub fn #fn_name() -> usize {
+                        #index
+                    }
+                });
+                num_constants += 1;
+            }
+        }
+    }
+
+    let wires_per_op = if is_static {
+        quote! {
+            pub fn wires_per_op() -> usize {
+                #offset
+            }
+        }
+    } else {
+        quote! {
+            pub fn wires_per_op(&self) -> usize {
+                #offset
+            }
+        }
+    };
+
+    let per_op = if is_static {
+        quote!(Self::wires_per_op())
+    } else {
+        quote!(self.wires_per_op())
+    };
+    let (num_ops_fn, num_wires_fn) = match &num_ops {
+        Some(field) => (
+            quote! {
+                /// Determine the maximum number of operations that can fit in one gate for the given config.
+                pub fn num_ops(config: &plonky2::plonk::circuit_data::CircuitConfig) -> usize {
+                    config.num_routed_wires / #per_op
+                }
+            },
+            quote! {
+                pub fn num_wires(&self) -> usize {
+                    self.#field * #per_op
+                }
+            },
+        ),
+        None => (
+            quote! {
+                pub fn num_ops(_config: &plonky2::plonk::circuit_data::CircuitConfig) -> usize {
+                    1
+                }
+            },
+            quote! {
+                pub fn num_wires(&self) -> usize {
+                    #per_op
+                }
+            },
+        ),
+    };
+
+    Ok(quote! {
+        impl #gate {
+            #wires_per_op
+
+            #(#functions)*
+
+            #num_ops_fn
+
+            #num_wires_fn
+
+            pub fn layout_num_constants() -> usize {
+                #num_constants
+            }
+        }
+    })
+}
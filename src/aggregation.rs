@@ -0,0 +1,422 @@
+//! Recursive aggregation of proofs of different circuits into one proof.
+//!
+//! Every recursion layer is a "commit circuit": it verifies a fixed number of children sharing the same
+//! `CommonCircuitData`, and its only public inputs are
+//! `H(pis_0 || vd_0 || pis_1 || vd_1 || ...)`, where `vd_i = H(constants_sigmas_cap || circuit_digest)`
+//! of child `i`. The verifier data of the children is a witness, committing to it keeps the layer sound.
+//!
+//! 1. Every inner proof is wrapped in a commit circuit of one child. The wrappers of different inner
+//!    shapes are padded to the same degree, and wrapped again until all of them share one shape.
+//! 2. The normalized proofs are folded pairwise by commit circuits of two children until one is left.
+//!    An odd proof out is paired with itself, like in Bitcoin's Merkle tree.
+//!
+//! `aggregate` also returns the verifier digests of the commit circuits of every layer, so that
+//! `expected_commitment` recomputes the final public inputs from the inner public inputs and verifier digests
+//! without rebuilding any commit circuit.
+
+use anyhow::{anyhow, Result};
+
+use plonky2::field::extension::Extendable;
+use plonky2::gates::noop::NoopGate;
+use plonky2::hash::hash_types::{HashOut, HashOutTarget, RichField};
+use plonky2::hash::poseidon::PoseidonHash;
+use plonky2::iop::witness::{PartialWitness, WitnessWrite};
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+use plonky2::plonk::circuit_data::{
+    CircuitConfig, CircuitData, CommonCircuitData, VerifierCircuitTarget, VerifierOnlyCircuitData,
+};
+use plonky2::plonk::config::{AlgebraicHasher, GenericConfig, Hasher};
+use plonky2::plonk::proof::ProofWithPublicInputsTarget;
+use plonky2_ecdsa::gadgets::recursive_proof::ProofTuple;
+
+/// Wrapping rounds after which the inner proofs must share one shape.
+const MAX_NORMALIZE_ROUNDS: usize = 3;
+
+/// `H(constants_sigmas_cap || circuit_digest)`
+pub fn verifier_digest<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    verifier_only: &VerifierOnlyCircuitData<C, D>,
+) -> HashOut<F>
+where
+    C::Hasher: AlgebraicHasher<F>,
+{
+    let elements: Vec<F> = verifier_only
+        .constants_sigmas_cap
+        .0
+        .iter()
+        .flat_map(|h| h.elements)
+        .chain(verifier_only.circuit_digest.elements)
+        .collect();
+    PoseidonHash::hash_no_pad(&elements)
+}
+
+fn verifier_digest_target<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    verifier_data: &VerifierCircuitTarget,
+) -> HashOutTarget {
+    let elements = verifier_data
+        .constants_sigmas_cap
+        .0
+        .iter()
+        .flat_map(|h| h.elements)
+        .chain(verifier_data.circuit_digest.elements)
+        .collect();
+    builder.hash_n_to_hash_no_pad::<PoseidonHash>(elements)
+}
+
+/// The public inputs of the commit circuit proving `children`.
+pub fn commitment<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    children: &[&ProofTuple<F, C, D>],
+) -> HashOut<F>
+where
+    C::Hasher: AlgebraicHasher<F>,
+{
+    let children: Vec<(&[F], HashOut<F>)> = children
+        .iter()
+        .map(|(proof, verifier_only, _)| (proof.public_inputs.as_slice(), verifier_digest(verifier_only)))
+        .collect();
+    commit(&children)
+}
+
+/// `H(pis_0 || vd_0 || pis_1 || vd_1 || ...)` of children given by their public inputs and verifier digest.
+fn commit<F: RichField>(children: &[(&[F], HashOut<F>)]) -> HashOut<F> {
+    let mut inputs = Vec::new();
+    for (public_inputs, digest) in children.iter() {
+        inputs.extend_from_slice(public_inputs);
+        inputs.extend(digest.elements);
+    }
+    PoseidonHash::hash_no_pad(&inputs)
+}
+
+/// The verifier digests of the commit circuits `aggregate` used, layer by layer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AggregationLayers<F: RichField> {
+    /// For every wrapping round, the digest of the wrapper of every proof.
+    pub wraps: Vec<Vec<HashOut<F>>>,
+    /// For every folding round, the digest of the commit circuit of two children.
+    pub folds: Vec<HashOut<F>>,
+}
+
+/// The public inputs of the proof `aggregate` returns for inner proofs with these public inputs and
+/// `verifier_digest`s, given the `layers` it returned.
+pub fn expected_commitment<F: RichField>(
+    inner: &[(Vec<F>, HashOut<F>)],
+    layers: &AggregationLayers<F>,
+) -> Result<Vec<F>> {
+    let mut layer = inner.to_vec();
+    for digests in layers.wraps.iter() {
+        if digests.len() != layer.len() {
+            return Err(anyhow!("a wrapping round of {} proofs for {} proofs", digests.len(), layer.len()));
+        }
+        layer = layer
+            .iter()
+            .zip(digests)
+            .map(|((public_inputs, digest), wrapper)| {
+                (commit(&[(public_inputs.as_slice(), *digest)]).elements.to_vec(), *wrapper)
+            })
+            .collect();
+    }
+    for fold in layers.folds.iter() {
+        layer = layer
+            .chunks(2)
+            .map(|pair| {
+                let (a, b) = (&pair[0], pair.last().unwrap());
+                (commit(&[(a.0.as_slice(), a.1), (b.0.as_slice(), b.1)]).elements.to_vec(), *fold)
+            })
+            .collect();
+    }
+
+    match layer.as_slice() {
+        [(public_inputs, _)] => Ok(public_inputs.clone()),
+        _ => Err(anyhow!("the layers leave {} proofs instead of one", layer.len())),
+    }
+}
+
+/// A recursion layer verifying `proofs.len()` children of one shape.
+pub struct CommitCircuit<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> {
+    pub data: CircuitData<F, C, D>,
+    /// The shape every child must have, the proof targets are laid out for it.
+    child_common: CommonCircuitData<F, D>,
+    proofs: Vec<ProofWithPublicInputsTarget<D>>,
+    verifier_data: Vec<VerifierCircuitTarget>,
+}
+
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> CommitCircuit<F, C, D>
+where
+    C::Hasher: AlgebraicHasher<F>,
+{
+    /// With `min_degree_bits`, the circuit is padded with `NoopGate`s to at least `2^min_degree_bits` rows.
+    pub fn new(
+        child_common: &CommonCircuitData<F, D>,
+        num_children: usize,
+        config: &CircuitConfig,
+        min_degree_bits: Option<usize>,
+    ) -> Result<Self> {
+        let mut builder = CircuitBuilder::<F, D>::new(config.clone());
+
+        let mut inputs = Vec::new();
+        let mut proofs = Vec::with_capacity(num_children);
+        let mut verifier_data = Vec::with_capacity(num_children);
+        for _ in 0..num_children {
+            let proof = builder.add_virtual_proof_with_pis(child_common);
+            let vd = builder.add_virtual_verifier_data(child_common.config.fri_config.cap_height);
+            builder.verify_proof::<C>(&proof, &vd, child_common);
+
+            inputs.extend_from_slice(&proof.public_inputs);
+            inputs.extend(verifier_digest_target(&mut builder, &vd).elements);
+            proofs.push(proof);
+            verifier_data.push(vd);
+        }
+
+        let commitment = builder.hash_n_to_hash_no_pad::<PoseidonHash>(inputs);
+        builder.register_public_inputs(&commitment.elements);
+
+        if let Some(min_degree_bits) = min_degree_bits {
+            // `build` still adds a few gates, so pad to just over half and let it round up to `2^min_degree_bits`.
+            let min_gates = (1 << (min_degree_bits - 1)) + 1;
+            for _ in builder.num_gates()..min_gates {
+                builder.add_gate(NoopGate, vec![]);
+            }
+        }
+
+        let data = builder.build::<C>();
+        if let Some(min_degree_bits) = min_degree_bits {
+            if data.common.degree_bits() < min_degree_bits {
+                return Err(anyhow!(
+                    "the padded commit circuit has 2^{} rows instead of at least 2^{min_degree_bits}",
+                    data.common.degree_bits()
+                ));
+            }
+        }
+
+        Ok(Self {
+            data,
+            child_common: child_common.clone(),
+            proofs,
+            verifier_data,
+        })
+    }
+
+    pub fn prove(&self, children: &[&ProofTuple<F, C, D>]) -> Result<ProofTuple<F, C, D>> {
+        if children.len() != self.proofs.len() {
+            return Err(anyhow!("expected {} children, got {}", self.proofs.len(), children.len()));
+        }
+        // A proof of another shape does not fit the proof targets, and the verifier would check it against the
+        // wrong `CommonCircuitData`.
+        if let Some(i) = children.iter().position(|(_, _, common)| *common != self.child_common) {
+            return Err(anyhow!(
+                "child {i} has another `CommonCircuitData` than the commit circuit was built for \
+                 ({} degree bits, {} expected)",
+                children[i].2.degree_bits(),
+                self.child_common.degree_bits()
+            ));
+        }
+
+        let mut pw = PartialWitness::new();
+        for (i, (proof, verifier_only, _)) in children.iter().enumerate() {
+            pw.set_proof_with_pis_target(&self.proofs[i], proof);
+            pw.set_verifier_data_target(&self.verifier_data[i], verifier_only);
+        }
+
+        let proof = self.data.prove(pw)?;
+        self.data.verify(proof.clone())?;
+        Ok((proof, self.data.verifier_only.clone(), self.data.common.clone()))
+    }
+}
+
+/// Wraps every child in a commit circuit of one child, all padded to the largest degree among them.
+fn wrap_all<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    children: &[ProofTuple<F, C, D>],
+    config: &CircuitConfig,
+) -> Result<Vec<ProofTuple<F, C, D>>>
+where
+    C::Hasher: AlgebraicHasher<F>,
+{
+    // One circuit per distinct child shape.
+    let mut shapes: Vec<&CommonCircuitData<F, D>> = Vec::new();
+    let mut shape_of = Vec::with_capacity(children.len());
+    for (_, _, common) in children.iter() {
+        let index = shapes.iter().position(|s| *s == common).unwrap_or_else(|| {
+            shapes.push(common);
+            shapes.len() - 1
+        });
+        shape_of.push(index);
+    }
+
+    let circuits: Vec<CommitCircuit<F, C, D>> = shapes
+        .iter()
+        .map(|s| CommitCircuit::new(s, 1, config, None))
+        .collect::<Result<_>>()?;
+    let degree_bits = circuits.iter().map(|c| c.data.common.degree_bits()).max().unwrap_or(0);
+    let circuits: Vec<CommitCircuit<F, C, D>> = circuits
+        .into_iter()
+        .zip(shapes.iter())
+        .map(|(c, s)| {
+            if c.data.common.degree_bits() < degree_bits {
+                CommitCircuit::new(s, 1, config, Some(degree_bits))
+            } else {
+                Ok(c)
+            }
+        })
+        .collect::<Result<_>>()?;
+
+    children
+        .iter()
+        .zip(shape_of)
+        .map(|(child, shape)| circuits[shape].prove(&[child]))
+        .collect()
+}
+
+/// Aggregates `inner` proofs, which may come from different circuits, into one proof whose public inputs
+/// commit to all inner public inputs and verifier data, see the module documentation.
+pub fn aggregate<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    inner: &[ProofTuple<F, C, D>],
+    config: &CircuitConfig,
+) -> Result<(ProofTuple<F, C, D>, AggregationLayers<F>)>
+where
+    C::Hasher: AlgebraicHasher<F>,
+{
+    if inner.is_empty() {
+        return Err(anyhow!("nothing to aggregate"));
+    }
+
+    let digests = |layer: &[ProofTuple<F, C, D>]| layer.iter().map(|(_, vd, _)| verifier_digest(vd)).collect();
+    let mut layer = wrap_all(inner, config)?;
+    let mut layers = AggregationLayers {
+        wraps: vec![digests(&layer)],
+        folds: Vec::new(),
+    };
+    while layer.iter().any(|(_, _, common)| *common != layer[0].2) {
+        if layers.wraps.len() == MAX_NORMALIZE_ROUNDS {
+            return Err(anyhow!(
+                "the inner proofs do not reach one shape after {} wrapping rounds",
+                layers.wraps.len()
+            ));
+        }
+        layer = wrap_all(&layer, config)?;
+        layers.wraps.push(digests(&layer));
+    }
+
+    // Folding only verifies children of the shape of the first one, `CommitCircuit::prove` checks the others.
+    while layer.len() > 1 {
+        let circuit = CommitCircuit::new(&layer[0].2, 2, config, None)?;
+        layer = layer
+            .chunks(2)
+            .map(|pair| circuit.prove(&[&pair[0], pair.last().unwrap()]))
+            .collect::<Result<_>>()?;
+        layers.folds.push(verifier_digest(&circuit.data.verifier_only));
+    }
+
+    Ok((layer.remove(0), layers))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use plonky2::field::types::Field;
+    use plonky2::plonk::circuit_data::VerifierCircuitData;
+    use plonky2::plonk::config::PoseidonGoldilocksConfig;
+
+    use crate::examples::Example;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    fn prove_example(example: Example, size: usize, value: u64) -> Result<ProofTuple<F, C, D>> {
+        let circuit = example.build::<F, C, D>(size)?;
+        let values: Vec<(String, u64)> = circuit.inputs.iter().map(|(n, _)| (n.clone(), value)).collect();
+        let proof = circuit.data.prove(circuit.witness(&values)?)?;
+        Ok((proof, circuit.data.verifier_only, circuit.data.common))
+    }
+
+    #[test]
+    fn wrapper_commitment() -> Result<()> {
+        let config = CircuitConfig::standard_recursion_config();
+        let inner = prove_example(Example::ArraySum, 3, 1)?;
+
+        let circuit = CommitCircuit::new(&inner.2, 1, &config, None)?;
+        let (proof, _, _) = circuit.prove(&[&inner])?;
+        assert_eq!(proof.public_inputs, commitment(&[&inner]).elements.to_vec());
+        Ok(())
+    }
+
+    #[test]
+    fn different_degrees() -> Result<()> {
+        let config = CircuitConfig::standard_recursion_config();
+        let inner = vec![
+            prove_example(Example::ArraySum, 3, 1)?,
+            prove_example(Example::MulAdd, 100, 1)?,
+        ];
+        assert!(inner[0].2.degree_bits() < inner[1].2.degree_bits());
+
+        // A commit circuit only takes children of the shape it was built for.
+        let circuit = CommitCircuit::new(&inner[0].2, 1, &config, None)?;
+        assert!(circuit.prove(&[&inner[1]]).is_err());
+
+        // The wrapper of the smaller circuit is padded to the degree of the other one.
+        let wrapped = wrap_all(&inner, &config)?;
+        assert_eq!(wrapped[0].2.degree_bits(), wrapped[1].2.degree_bits());
+
+        let ((proof, verifier_only, common), _) = aggregate(&inner, &config)?;
+        assert_eq!(proof.public_inputs, commitment(&[&wrapped[0], &wrapped[1]]).elements.to_vec());
+        VerifierCircuitData { verifier_only, common }.verify(proof)
+    }
+
+    #[test]
+    fn heterogeneous() -> Result<()> {
+        let config = CircuitConfig::standard_recursion_config();
+        let inner = vec![
+            prove_example(Example::ArraySum, 3, 1)?,
+            prove_example(Example::ArraySum, 2, 1)?,
+            prove_example(Example::MulAdd, 1, 1)?,
+        ];
+        // The array sums share one shape, the mul-add circuit has another.
+        assert_eq!(inner[0].2, inner[1].2);
+        assert_ne!(inner[0].2, inner[2].2);
+
+        let ((proof, verifier_only, common), layers) = aggregate(&inner, &config)?;
+        assert_eq!(proof.public_inputs.len(), 4);
+        VerifierCircuitData {
+            verifier_only: verifier_only.clone(),
+            common: common.clone(),
+        }
+        .verify(proof.clone())?;
+
+        // Another inner public input changes the commitment, but not the final circuit.
+        let mut other = inner;
+        other[1] = prove_example(Example::ArraySum, 2, 2)?;
+        let ((other_proof, other_verifier_only, _), other_layers) = aggregate(&other, &config)?;
+        assert_eq!(other_verifier_only, verifier_only);
+        assert_eq!(other_layers, layers);
+        assert_ne!(other_proof.public_inputs, proof.public_inputs);
+        Ok(())
+    }
+
+    #[test]
+    fn expected_public_inputs() -> Result<()> {
+        let config = CircuitConfig::standard_recursion_config();
+        let inner = vec![
+            prove_example(Example::ArraySum, 3, 1)?,
+            prove_example(Example::MulAdd, 1, 2)?,
+            prove_example(Example::ArraySum, 2, 3)?,
+        ];
+        let ((proof, _, _), layers) = aggregate(&inner, &config)?;
+
+        // Only the inner public inputs and verifier digests, plus the digests of the layers.
+        let claims: Vec<(Vec<F>, HashOut<F>)> = inner
+            .iter()
+            .map(|(proof, verifier_only, _)| (proof.public_inputs.clone(), verifier_digest(verifier_only)))
+            .collect();
+        assert_eq!(proof.public_inputs, expected_commitment(&claims, &layers)?);
+
+        // Another inner public input or inner circuit gives other public inputs.
+        let mut wrong = claims.clone();
+        wrong[2].0[0] += F::ONE;
+        assert_ne!(proof.public_inputs, expected_commitment(&wrong, &layers)?);
+        let mut wrong = claims;
+        wrong.swap(0, 2);
+        assert_ne!(proof.public_inputs, expected_commitment(&wrong, &layers)?);
+        Ok(())
+    }
+}
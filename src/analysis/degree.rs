@@ -0,0 +1,165 @@
+use core::fmt;
+
+use plonky2::field::extension::{Extendable, FieldExtension};
+use plonky2::field::polynomial::PolynomialValues;
+use plonky2::field::types::{Field, Sample};
+use plonky2::gates::gate::Gate;
+use plonky2::hash::hash_types::{HashOut, RichField};
+use plonky2::plonk::circuit_data::CircuitConfig;
+use plonky2::plonk::vars::EvaluationVars;
+use plonky2::util::log2_ceil;
+
+/// Constraints of a higher degree than `2^MAX_LOG_POINTS - 2` are reported as this bound.
+const MAX_LOG_POINTS: usize = 10;
+
+#[derive(Debug, Clone)]
+pub struct DegreeReport {
+    pub gate: String,
+    /// `Gate::degree()`
+    pub declared: usize,
+    /// The largest degree of `constraint_degrees`.
+    pub measured: usize,
+    /// The degree of every constraint in the wires and constants.
+    pub constraint_degrees: Vec<usize>,
+    /// The largest gate degree the config can handle, `max_quotient_degree_factor + 1`.
+    /// The selector polynomials make the real bound of a circuit lower than this.
+    pub max_degree: usize,
+}
+
+impl DegreeReport {
+    /// The quotient polynomial is bigger than needed.
+    pub fn is_over_declared(&self) -> bool {
+        self.measured < self.declared
+    }
+
+    /// The quotient polynomial is too small, the constraints can not be checked soundly.
+    pub fn is_under_declared(&self) -> bool {
+        self.measured > self.declared
+    }
+
+    pub fn exceeds_max_degree(&self) -> bool {
+        self.declared.max(self.measured) > self.max_degree
+    }
+
+    pub fn is_ok(&self) -> bool {
+        !self.is_over_declared() && !self.is_under_declared() && !self.exceeds_max_degree()
+    }
+}
+
+impl fmt::Display for DegreeReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: declared degree {}, measured degree {}", self.gate, self.declared, self.measured)?;
+        if self.is_over_declared() {
+            write!(f, ", the declared degree is too high")?;
+        }
+        if self.is_under_declared() {
+            write!(f, ", the declared degree is too low")?;
+        }
+        if self.exceeds_max_degree() {
+            write!(f, ", more than the max degree {} of the config", self.max_degree)?;
+        }
+        writeln!(f)
+    }
+}
+
+/// The degree of every constraint of `gate`, found by evaluating `eval_unfiltered` on `2^log_points` points
+/// of random linear wire and constant polynomials, and interpolating the results.
+/// A constraint of degree `2^log_points - 1` or more is not measured correctly.
+pub fn constraint_degrees<F: RichField + Extendable<D>, G: Gate<F, D>, const D: usize>(
+    gate: &G,
+    log_points: usize,
+) -> Vec<usize> {
+    let linear = |n: usize| -> Vec<(F, F)> { (0..n).map(|_| (F::rand(), F::rand())).collect() };
+    let wires = linear(gate.num_wires());
+    let constants = linear(gate.num_constants());
+    let public_inputs_hash = HashOut::rand();
+
+    let mut values = vec![vec![Vec::with_capacity(1 << log_points); D]; gate.num_constraints()];
+    for x in F::two_adic_subgroup(log_points) {
+        let at_x = |p: &[(F, F)]| -> Vec<F::Extension> { p.iter().map(|&(a, b)| (a + b * x).into()).collect() };
+        let local_wires = at_x(&wires);
+        let local_constants = at_x(&constants);
+
+        let constraints = gate.eval_unfiltered(EvaluationVars {
+            local_constants: &local_constants,
+            local_wires: &local_wires,
+            public_inputs_hash: &public_inputs_hash,
+        });
+        for (i, c) in constraints.into_iter().enumerate() {
+            for (j, limb) in c.to_basefield_array().into_iter().enumerate() {
+                values[i][j].push(limb);
+            }
+        }
+    }
+
+    values
+        .into_iter()
+        .map(|limbs| {
+            limbs
+                .into_iter()
+                .map(|v| PolynomialValues::new(v).ifft().degree_plus_one().saturating_sub(1))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect()
+}
+
+/// Compares the measured degree of `gate` with `Gate::degree()` and the bound of `config`.
+pub fn measure_degree<F: RichField + Extendable<D>, G: Gate<F, D>, const D: usize>(
+    gate: &G,
+    config: &CircuitConfig,
+) -> DegreeReport {
+    let declared = gate.degree();
+
+    // Start with enough points for the declared degree, and double them while a constraint looks like it wraps around.
+    let mut log_points = log2_ceil(declared + 2).max(2);
+    let constraint_degrees = loop {
+        let degrees = constraint_degrees(gate, log_points);
+        if log_points >= MAX_LOG_POINTS || degrees.iter().all(|&d| d + 1 < 1 << log_points) {
+            break degrees;
+        }
+        log_points += 1;
+    };
+
+    DegreeReport {
+        gate: gate.id(),
+        declared,
+        measured: constraint_degrees.iter().copied().max().unwrap_or(0),
+        constraint_degrees,
+        max_degree: config.max_quotient_degree_factor + 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use plonky2::field::goldilocks_field::GoldilocksField;
+
+    use crate::gates::expression_gate::{Expr, Expression, ExpressionGate};
+    use crate::gates::gate_with_veriable_vars::SimpleExpTestGate;
+
+    const D: usize = 2;
+    type F = GoldilocksField;
+
+    #[test]
+    fn constraint_degrees_of_exp_gate() {
+        let gate = SimpleExpTestGate::new(6, &CircuitConfig::standard_recursion_config());
+        // `power_i - base^i` for every power, then `base^6 - output`
+        assert_eq!(constraint_degrees::<F, _, D>(&gate, 4), vec![1, 2, 3, 4, 5, 6, 6]);
+    }
+
+    #[test]
+    fn over_declared() {
+        let config = CircuitConfig::standard_recursion_config();
+        // The products cancel out, only the output wire is left.
+        let expr = Expr::wire("x") * Expr::wire("x") - Expr::wire("x") * Expr::wire("x");
+        let gate = ExpressionGate::new_from_config(Expression::new(&["x"], "out", &[], expr), &config);
+
+        let report = measure_degree::<F, _, D>(&gate, &config);
+        assert_eq!(report.declared, 2);
+        assert_eq!(report.measured, 1);
+        assert!(report.is_over_declared());
+        assert!(!report.is_under_declared());
+    }
+}
@@ -0,0 +1,199 @@
+use core::fmt;
+use core::fmt::Debug;
+
+use plonky2::field::extension::Extendable;
+use plonky2::hash::hash_types::RichField;
+use plonky2::plonk::circuit_data::{CommonCircuitData, VerifierOnlyCircuitData};
+use plonky2::plonk::config::GenericConfig;
+
+/// One field which differs between two circuits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Difference {
+    pub field: String,
+    pub a: String,
+    pub b: String,
+    /// Whether a recursive verifier built for one circuit can not verify proofs of the other.
+    pub breaks_recursion: bool,
+    pub reason: &'static str,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CommonDataDiff {
+    pub differences: Vec<Difference>,
+}
+
+impl CommonDataDiff {
+    pub fn is_identical(&self) -> bool {
+        self.differences.is_empty()
+    }
+
+    /// Whether one recursive verifier can verify proofs of both circuits.
+    pub fn is_recursively_compatible(&self) -> bool {
+        self.differences.iter().all(|d| !d.breaks_recursion)
+    }
+
+    fn push<T: Debug + PartialEq>(&mut self, field: &str, a: T, b: T, breaks_recursion: bool, reason: &'static str) {
+        if a != b {
+            self.differences.push(Difference {
+                field: field.to_string(),
+                a: format!("{a:?}"),
+                b: format!("{b:?}"),
+                breaks_recursion,
+                reason,
+            });
+        }
+    }
+}
+
+impl fmt::Display for CommonDataDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_identical() {
+            return writeln!(f, "the circuits have the same shape");
+        }
+
+        for d in self.differences.iter() {
+            let tag = if d.breaks_recursion { "breaks recursion" } else { "compatible" };
+            writeln!(f, "{}: {} != {} ({}: {})", d.field, d.a, d.b, tag, d.reason)?;
+        }
+        if self.is_recursively_compatible() {
+            writeln!(f, "one recursive verifier can verify both circuits")
+        } else {
+            writeln!(f, "the circuits need different recursive verifiers")
+        }
+    }
+}
+
+/// Compares the shapes of two circuits, and explains which differences make them need different recursive verifiers.
+pub fn diff_common_data<F: RichField + Extendable<D>, const D: usize>(
+    a: &CommonCircuitData<F, D>,
+    b: &CommonCircuitData<F, D>,
+) -> CommonDataDiff {
+    let mut diff = CommonDataDiff::default();
+
+    const OPENINGS: &str = "the number of opened polynomials changes";
+    const ONLY_THROUGH_OTHERS: &str = "it only matters through the other fields";
+    let (ac, bc) = (&a.config, &b.config);
+    diff.push("config.num_wires", ac.num_wires, bc.num_wires, true, OPENINGS);
+    diff.push("config.num_routed_wires", ac.num_routed_wires, bc.num_routed_wires, true, OPENINGS);
+    diff.push("config.num_constants", ac.num_constants, bc.num_constants, true, OPENINGS);
+    diff.push("config.num_challenges", ac.num_challenges, bc.num_challenges, true, "the verifier draws a different number of challenges");
+    diff.push("config.security_bits", ac.security_bits, bc.security_bits, false, ONLY_THROUGH_OTHERS);
+    diff.push("config.zero_knowledge", ac.zero_knowledge, bc.zero_knowledge, false, ONLY_THROUGH_OTHERS);
+    diff.push("config.use_base_arithmetic_gate", ac.use_base_arithmetic_gate, bc.use_base_arithmetic_gate, false, ONLY_THROUGH_OTHERS);
+    diff.push("config.max_quotient_degree_factor", ac.max_quotient_degree_factor, bc.max_quotient_degree_factor, false, ONLY_THROUGH_OTHERS);
+
+    const FRI: &str = "the FRI verification follows a different schedule";
+    let (af, bf) = (&ac.fri_config, &bc.fri_config);
+    diff.push("config.fri_config.rate_bits", af.rate_bits, bf.rate_bits, true, FRI);
+    diff.push("config.fri_config.cap_height", af.cap_height, bf.cap_height, true, "the Merkle caps have a different size");
+    diff.push("config.fri_config.proof_of_work_bits", af.proof_of_work_bits, bf.proof_of_work_bits, true, FRI);
+    diff.push("config.fri_config.num_query_rounds", af.num_query_rounds, bf.num_query_rounds, true, FRI);
+    diff.push(
+        "config.fri_config.reduction_strategy",
+        format!("{:?}", af.reduction_strategy),
+        format!("{:?}", bf.reduction_strategy),
+        false,
+        ONLY_THROUGH_OTHERS,
+    );
+
+    diff.push("degree_bits", a.degree_bits(), b.degree_bits(), true, "the trace and FRI domains have a different size");
+    diff.push("fri_params.hiding", a.fri_params.hiding, b.fri_params.hiding, true, "the blinding polynomials are opened or not");
+    diff.push(
+        "fri_params.reduction_arity_bits",
+        &a.fri_params.reduction_arity_bits,
+        &b.fri_params.reduction_arity_bits,
+        true,
+        FRI,
+    );
+
+    let a_gates: Vec<String> = a.gates.iter().map(|g| g.0.id()).collect();
+    let b_gates: Vec<String> = b.gates.iter().map(|g| g.0.id()).collect();
+    let only_a: Vec<&String> = a_gates.iter().filter(|g| !b_gates.contains(g)).collect();
+    let only_b: Vec<&String> = b_gates.iter().filter(|g| !a_gates.contains(g)).collect();
+    const CONSTRAINTS: &str = "the verifier evaluates other gate constraints";
+    if !only_a.is_empty() || !only_b.is_empty() {
+        diff.push("gates (only in a / only in b)", only_a, only_b, true, CONSTRAINTS);
+    } else {
+        diff.push("gates (order)", &a_gates, &b_gates, true, "the gates are selected by their index");
+    }
+
+    const SELECTORS: &str = "the gates are filtered by other selector polynomials";
+    diff.push("selectors_info.selector_indices", &a.selectors_info.selector_indices, &b.selectors_info.selector_indices, true, SELECTORS);
+    diff.push("selectors_info.groups", &a.selectors_info.groups, &b.selectors_info.groups, true, SELECTORS);
+    diff.push("num_lookup_selectors", a.num_lookup_selectors, b.num_lookup_selectors, true, SELECTORS);
+
+    diff.push("num_public_inputs", a.num_public_inputs, b.num_public_inputs, true, "the verifier hashes a different number of public inputs");
+    diff.push("num_constants", a.num_constants, b.num_constants, true, OPENINGS);
+    diff.push("num_gate_constraints", a.num_gate_constraints, b.num_gate_constraints, true, CONSTRAINTS);
+    diff.push("quotient_degree_factor", a.quotient_degree_factor, b.quotient_degree_factor, true, OPENINGS);
+    diff.push("num_partial_products", a.num_partial_products, b.num_partial_products, true, OPENINGS);
+    diff.push("num_lookup_polys", a.num_lookup_polys, b.num_lookup_polys, true, OPENINGS);
+    diff.push("k_is", &a.k_is, &b.k_is, true, "the copy constraints are checked with other cosets");
+
+    diff
+}
+
+/// Compares the verifier data of two circuits of the same shape.
+/// A recursive verifier taking the verifier data as a witness accepts both, one with constant verifier data does not.
+pub fn diff_verifier_only<C: GenericConfig<D>, const D: usize>(
+    a: &VerifierOnlyCircuitData<C, D>,
+    b: &VerifierOnlyCircuitData<C, D>,
+) -> CommonDataDiff {
+    const WITNESS: &str = "only a verifier with constant verifier data tells them apart";
+    let mut diff = CommonDataDiff::default();
+    diff.push("constants_sigmas_cap", &a.constants_sigmas_cap, &b.constants_sigmas_cap, false, WITNESS);
+    diff.push("circuit_digest", &a.circuit_digest, &b.circuit_digest, false, WITNESS);
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use anyhow::Result;
+
+    use plonky2::plonk::config::PoseidonGoldilocksConfig;
+
+    use crate::examples::Example;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    #[test]
+    fn same_circuit() -> Result<()> {
+        let a = Example::ArraySum.build::<F, C, D>(3)?;
+        let b = Example::ArraySum.build::<F, C, D>(3)?;
+        assert!(diff_common_data(&a.data.common, &b.data.common).is_identical());
+        assert!(diff_verifier_only(&a.data.verifier_only, &b.data.verifier_only).is_identical());
+        Ok(())
+    }
+
+    #[test]
+    fn array_sums() -> Result<()> {
+        // Both sums fit in one `ArithmeticGate` row, so only the wiring differs.
+        let a = Example::ArraySum.build::<F, C, D>(2)?;
+        let b = Example::ArraySum.build::<F, C, D>(3)?;
+
+        let diff = diff_common_data(&a.data.common, &b.data.common);
+        assert!(diff.is_identical(), "{diff}");
+
+        let verifier_diff = diff_verifier_only(&a.data.verifier_only, &b.data.verifier_only);
+        let fields: Vec<&str> = verifier_diff.differences.iter().map(|d| d.field.as_str()).collect();
+        assert_eq!(fields, ["constants_sigmas_cap", "circuit_digest"]);
+        assert!(verifier_diff.is_recursively_compatible());
+        Ok(())
+    }
+
+    #[test]
+    fn different_examples() -> Result<()> {
+        let a = Example::ArraySum.build::<F, C, D>(3)?;
+        let b = Example::MulAdd.build::<F, C, D>(2)?;
+
+        let diff = diff_common_data(&a.data.common, &b.data.common);
+        assert!(!diff.is_recursively_compatible(), "{diff}");
+        assert!(diff.differences.iter().any(|d| d.field == "num_public_inputs"));
+        assert!(diff.differences.iter().any(|d| d.field.starts_with("gates")));
+        Ok(())
+    }
+}
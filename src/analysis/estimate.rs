@@ -0,0 +1,292 @@
+//! The cost of a circuit predicted from its builder, before `build`.
+//!
+//! The estimate replays what `build` adds to the gate instances: the Poseidon rows hashing the public inputs,
+//! the `PublicInputGate` row, a `ConstantGate` row for `zero()` and `one()`, and the `NoopGate` padding to a
+//! power of two. From the padded degree it derives the selector groups like plonky2 does, the size of the
+//! proof from the FRI parameters of the config, and the cells of the low-degree extensions, which the proving
+//! time is proportional to.
+//!
+//! The builder does not expose its constants, so a single `ConstantGate` row is assumed: it holds
+//! `num_constants` of them, 2 in the standard configs, enough for the `zero()` and `one()` of most circuits.
+//! The blinding rows and salts of a `zero_knowledge` config are not counted.
+
+use core::fmt;
+use core::time::Duration;
+
+use plonky2::field::extension::Extendable;
+use plonky2::gates::constant::ConstantGate;
+use plonky2::gates::gate::GateRef;
+use plonky2::gates::noop::NoopGate;
+use plonky2::gates::poseidon::PoseidonGate;
+use plonky2::gates::public_input::PublicInputGate;
+use plonky2::hash::hash_types::{RichField, NUM_HASH_OUT_ELTS};
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+use plonky2::plonk::circuit_data::CircuitConfig;
+
+/// The rate of the Poseidon sponge hashing the public inputs.
+const SPONGE_RATE: usize = 8;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CostEstimate {
+    /// The rows before the padding to a power of two.
+    pub rows: usize,
+    pub degree_bits: usize,
+    /// The id of every gate type, sorted by degree then id like `build` does.
+    pub gate_types: Vec<String>,
+    pub num_selectors: usize,
+    /// The selectors and the constants of the gates.
+    pub num_constants: usize,
+    /// The size in bytes of `ProofWithPublicInputs::to_bytes`.
+    pub proof_size: usize,
+    /// The evaluations of the four committed oracles on the LDE domain, which dominate the proving time.
+    pub lde_cells: usize,
+}
+
+impl CostEstimate {
+    /// The proving time on a machine which spends `nanos_per_lde_cell` per LDE cell. The rate depends on the
+    /// machine and the build profile: measure it once by proving any circuit and dividing the time by the
+    /// `lde_cells` of its estimate.
+    pub fn proving_time(&self, nanos_per_lde_cell: f64) -> Duration {
+        Duration::from_secs_f64(self.lde_cells as f64 * nanos_per_lde_cell * 1e-9)
+    }
+}
+
+impl fmt::Display for CostEstimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "rows: {} (2^{} after padding)", self.rows, self.degree_bits)?;
+        writeln!(f, "selectors: {}", self.num_selectors)?;
+        writeln!(f, "constants: {}", self.num_constants)?;
+        writeln!(f, "proof size: {} bytes", self.proof_size)?;
+        writeln!(f, "LDE cells: {}", self.lde_cells)?;
+        writeln!(f, "gates:")?;
+        for gate in self.gate_types.iter() {
+            writeln!(f, "    {gate}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Estimates the cost of `builder` if it were built now, with its own config.
+pub fn estimate_cost<F: RichField + Extendable<D>, const D: usize>(builder: &CircuitBuilder<F, D>) -> CostEstimate {
+    estimate_cost_with_pending(builder, &[])
+}
+
+/// `estimate_cost` with `pending` rows of gates which are not added to `builder` yet, e.g. the recorded ops of
+/// a `FusingBuilder`.
+pub fn estimate_cost_with_pending<F: RichField + Extendable<D>, const D: usize>(
+    builder: &CircuitBuilder<F, D>,
+    pending: &[(GateRef<F, D>, usize)],
+) -> CostEstimate {
+    let config = &builder.config;
+    let mut gates: Vec<GateRef<F, D>> = Vec::new();
+    let mut add = |gate: &GateRef<F, D>| {
+        if gates.iter().all(|g| g.0.id() != gate.0.id()) {
+            gates.push(gate.clone());
+        }
+    };
+
+    for i in 0..builder.num_gates() {
+        add(&builder.get_gate_instance(i).gate_ref);
+    }
+    let mut rows = builder.num_gates();
+    for (gate, gate_rows) in pending.iter().filter(|(_, r)| *r > 0) {
+        add(gate);
+        rows += gate_rows;
+    }
+
+    // What `build` adds before padding.
+    let permutations = builder.num_public_inputs().div_ceil(SPONGE_RATE);
+    if permutations > 0 {
+        add(&GateRef::new(PoseidonGate::<F, D>::new()));
+    }
+    add(&GateRef::new(PublicInputGate));
+    add(&GateRef::new(ConstantGate::new(config.num_constants)));
+    rows += permutations + 2;
+
+    let degree = rows.next_power_of_two();
+    if degree > rows {
+        add(&GateRef::new(NoopGate));
+    }
+    let degree_bits = degree.trailing_zeros() as usize;
+
+    gates.sort_by_key(|g| (g.0.degree(), g.0.id()));
+    let num_selectors = num_selectors(&gates, config.max_quotient_degree_factor + 1);
+    let num_constants = num_selectors + gates.iter().map(|g| g.0.num_constants()).max().unwrap_or(0);
+
+    let lde_cells = (degree << config.fri_config.rate_bits) * oracle_widths(config, num_constants).iter().sum::<usize>();
+
+    CostEstimate {
+        rows,
+        degree_bits,
+        gate_types: gates.iter().map(|g| g.0.id()).collect(),
+        num_selectors,
+        num_constants,
+        proof_size: proof_size::<D>(config, degree_bits, num_constants, builder.num_public_inputs()),
+        lde_cells,
+    }
+}
+
+/// The selector groups of plonky2's `selector_polynomials`, for `gates` sorted by degree.
+fn num_selectors<F: RichField + Extendable<D>, const D: usize>(gates: &[GateRef<F, D>], max_degree: usize) -> usize {
+    let max_gate_degree = gates.last().map_or(0, |g| g.0.degree());
+    if max_gate_degree + gates.len() - 1 <= max_degree {
+        return 1;
+    }
+
+    let mut groups = 0;
+    let mut start = 0;
+    while start < gates.len() {
+        let mut size = 0;
+        while start + size < gates.len() && size + gates[start + size].0.degree() < max_degree {
+            size += 1;
+        }
+        groups += 1;
+        start += size;
+    }
+    groups
+}
+
+fn num_partial_products(config: &CircuitConfig) -> usize {
+    config.num_routed_wires.div_ceil(config.max_quotient_degree_factor) - 1
+}
+
+/// The polynomials of the four committed oracles: constants and sigmas, wires, Z and partial products, quotient.
+fn oracle_widths(config: &CircuitConfig, num_constants: usize) -> [usize; 4] {
+    let challenges = config.num_challenges;
+    [
+        num_constants + config.num_routed_wires,
+        config.num_wires,
+        challenges * (1 + num_partial_products(config)),
+        challenges * config.max_quotient_degree_factor,
+    ]
+}
+
+/// Every field element of an uncompressed proof with its public inputs, 8 bytes each.
+fn proof_size<const D: usize>(
+    config: &CircuitConfig,
+    degree_bits: usize,
+    num_constants: usize,
+    num_public_inputs: usize,
+) -> usize {
+    let fri = &config.fri_config;
+    let params = fri.fri_params(degree_bits, config.zero_knowledge);
+    let cap = (1 << fri.cap_height) * NUM_HASH_OUT_ELTS;
+    let path = |height: usize| height.saturating_sub(fri.cap_height) * NUM_HASH_OUT_ELTS;
+    let widths = oracle_widths(config, num_constants);
+
+    // Wires, Z and partial products, quotient.
+    let caps = 3 * cap;
+
+    // The openings at zeta, and of the Zs at g * zeta.
+    let challenges = config.num_challenges;
+    let openings = D * (widths.iter().sum::<usize>() + challenges);
+
+    let lde_bits = degree_bits + fri.rate_bits;
+    let mut query = widths.iter().map(|w| w + path(lde_bits)).sum::<usize>();
+    let mut height = lde_bits;
+    for &arity_bits in params.reduction_arity_bits.iter() {
+        height -= arity_bits;
+        query += D * (1 << arity_bits) + path(height);
+    }
+    let commit_caps = params.reduction_arity_bits.len() * cap;
+    let final_poly = D * params.final_poly_len();
+    let fri_proof = commit_caps + fri.num_query_rounds * query + final_poly + 1;
+
+    8 * (caps + openings + fri_proof + num_public_inputs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use anyhow::Result;
+
+    use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+
+    use crate::analysis::stats::circuit_stats;
+    use crate::builder::FusingBuilder;
+    use crate::examples::Example;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    #[test]
+    fn examples() -> Result<()> {
+        for example in Example::ALL {
+            for size in [1, example.default_size()] {
+                let example_builder = example.builder::<F, D>(size)?;
+                let estimate = estimate_cost(&example_builder.builder);
+
+                let circuit = example_builder.build::<C>();
+                let common = &circuit.data.common;
+                let stats = circuit_stats(&circuit.data);
+                let rows: usize = stats
+                    .gate_rows
+                    .iter()
+                    .filter(|(gate, _)| !gate.starts_with("NoopGate"))
+                    .map(|(_, r)| r)
+                    .sum();
+
+                // The examples only use the constants `zero()` and `one()`, so the single `ConstantGate` row of
+                // the estimate is exact.
+                assert_eq!(rows, estimate.rows, "{example} {size}");
+                assert_eq!(estimate.degree_bits, common.degree_bits(), "{example} {size}");
+                assert_eq!(estimate.num_selectors, common.selectors_info.num_selectors(), "{example} {size}");
+                assert_eq!(estimate.num_constants, common.num_constants, "{example} {size}");
+                let mut gate_types: Vec<String> = common.gates.iter().map(|g| g.0.id()).collect();
+                gate_types.sort();
+                let mut estimated_types = estimate.gate_types.clone();
+                estimated_types.sort();
+                assert_eq!(estimated_types, gate_types, "{example} {size}");
+
+                let values: Vec<(String, u64)> = circuit.inputs.iter().map(|(n, _)| (n.clone(), 1)).collect();
+                let proof = circuit.data.prove(circuit.witness(&values)?)?;
+                let proof_size = proof.to_bytes().len();
+                assert!(
+                    proof_size.abs_diff(estimate.proof_size) * 10 <= proof_size,
+                    "{example} {size}: {proof_size} bytes, estimated {}",
+                    estimate.proof_size
+                );
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn grows_with_rows() -> Result<()> {
+        let small = estimate_cost(&Example::MulAdd.builder::<F, D>(1)?.builder);
+        let large = estimate_cost(&Example::MulAdd.builder::<F, D>(1000)?.builder);
+
+        assert!(large.rows > small.rows);
+        assert!(large.degree_bits > small.degree_bits);
+        assert!(large.proof_size > small.proof_size);
+        assert!(large.lde_cells > small.lde_cells);
+        assert!(large.proving_time(40.0) > small.proving_time(40.0));
+        Ok(())
+    }
+
+    #[test]
+    fn fusing_pending_ops() -> Result<()> {
+        let mut builder = FusingBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        for _ in 0..40 {
+            let t: Vec<_> = (0..5).map(|_| builder.add_virtual_target()).collect();
+            let xy = builder.mul(t[0], t[1]);
+            let sum = builder.add(xy, t[2]);
+            let sum = builder.add(sum, t[3]);
+            let output = builder.add(sum, t[4]);
+            builder.register_public_input(output);
+        }
+
+        // The recorded ops are not on the builder yet.
+        let estimate = builder.estimate();
+        assert!(estimate.rows > estimate_cost(&builder).rows);
+        assert!(estimate.gate_types.iter().any(|g| g.starts_with("SimpleMulAddTestGate")));
+
+        let (data, _) = builder.build::<C>()?;
+        assert_eq!(estimate.degree_bits, data.common.degree_bits());
+        assert_eq!(estimate.num_selectors, data.common.selectors_info.num_selectors());
+        Ok(())
+    }
+}
@@ -0,0 +1,631 @@
+use core::fmt;
+use std::collections::HashMap;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use plonky2::field::extension::Extendable;
+use plonky2::field::types::Sample;
+use plonky2::hash::hash_types::RichField;
+use plonky2::iop::generator::{GeneratedValues, WitnessGeneratorRef};
+use plonky2::iop::target::Target;
+use plonky2::iop::witness::{PartitionWitness, WitnessWrite};
+use plonky2::plonk::circuit_data::{CircuitConfig, CircuitData};
+use plonky2::plonk::config::GenericConfig;
+
+/// What a generator reads and writes, found by running it alone on random dependency values.
+#[derive(Debug, Clone)]
+pub struct GeneratorInfo {
+    pub index: usize,
+    pub id: String,
+    pub dependencies: Vec<Target>,
+    pub outputs: Vec<Target>,
+}
+
+#[derive(Debug, Clone)]
+pub enum GeneratorIssue {
+    /// The generator waits for targets it writes itself, so it can only run if someone else sets them first.
+    SelfDependency { generator: usize, targets: Vec<Target> },
+    /// The generator panicked although all its dependencies were set,
+    /// e.g. because it reads a target it just wrote or one it does not declare.
+    Panicked { generator: usize, message: String },
+    /// The generator did not finish although all its dependencies were set.
+    DidNotRun { generator: usize },
+    /// These generators wait for each other, directly or through a chain of generators.
+    /// One issue per strongly connected component of the dependency graph.
+    Cycle { generators: Vec<usize> },
+    /// These generators are not in a cycle but wait, directly or not, for a generator in one or for a
+    /// generator depending on itself, which plonky2 never runs either.
+    Stuck { generators: Vec<usize> },
+    /// The target is written by more than one generator.
+    MultipleProducers { target: Target, generators: Vec<usize> },
+    /// The target is a dependency but no generator writes it and it is not a declared input.
+    NeverProduced { target: Target, consumers: Vec<usize> },
+}
+
+#[derive(Debug, Clone)]
+pub struct GeneratorReport {
+    pub generators: Vec<GeneratorInfo>,
+    pub issues: Vec<GeneratorIssue>,
+}
+
+impl GeneratorReport {
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+impl fmt::Display for GeneratorReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let id = |i: &usize| self.generators[*i].id.as_str();
+        for issue in self.issues.iter() {
+            match issue {
+                GeneratorIssue::SelfDependency { generator, targets } => {
+                    writeln!(f, "{} depends on its own outputs {:?}", id(generator), targets)?
+                }
+                GeneratorIssue::Panicked { generator, message } => {
+                    writeln!(f, "{} panicked with its dependencies set: {}", id(generator), message)?
+                }
+                GeneratorIssue::DidNotRun { generator } => {
+                    writeln!(f, "{} did not run with its dependencies set", id(generator))?
+                }
+                GeneratorIssue::Cycle { generators } => {
+                    let ids: Vec<&str> = generators.iter().map(id).collect();
+                    writeln!(f, "dependency cycle between {:?}", ids)?
+                }
+                GeneratorIssue::Stuck { generators } => {
+                    let ids: Vec<&str> = generators.iter().map(id).collect();
+                    writeln!(f, "{:?} wait for a dependency cycle or a self-dependent generator", ids)?
+                }
+                GeneratorIssue::MultipleProducers { target, generators } => {
+                    let ids: Vec<&str> = generators.iter().map(id).collect();
+                    writeln!(f, "{:?} is written by {:?}", target, ids)?
+                }
+                GeneratorIssue::NeverProduced { target, consumers } => {
+                    let ids: Vec<&str> = consumers.iter().map(id).collect();
+                    writeln!(f, "{:?} is never written but needed by {:?}", target, ids)?
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Checks the generators of a built circuit. `inputs` are the targets set by the caller's `PartialWitness`.
+pub fn validate_circuit_generators<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    data: &CircuitData<F, C, D>,
+    inputs: &[Target],
+) -> GeneratorReport {
+    validate_generators(
+        &data.prover_only.generators,
+        data.common.config.num_wires,
+        data.common.degree(),
+        &data.prover_only.representative_map,
+        inputs,
+    )
+}
+
+/// Checks a plain list of generators, e.g. the ones of a single gate.
+/// Without a built circuit there are no copy constraints, so every target is its own representative.
+pub fn validate_generator_list<F: RichField + Extendable<D>, const D: usize>(
+    generators: &[WitnessGeneratorRef<F, D>],
+    config: &CircuitConfig,
+    inputs: &[Target],
+) -> GeneratorReport {
+    let num_wires = config.num_wires;
+    let mut degree = 1;
+    let mut num_virtual_targets = 0;
+    for t in generators.iter().flat_map(|g| g.0.watch_list()).chain(inputs.iter().copied()) {
+        match t {
+            Target::Wire(w) => degree = degree.max(w.row + 1),
+            Target::VirtualTarget { index } => num_virtual_targets = num_virtual_targets.max(index + 1),
+        }
+    }
+
+    let representative_map: Vec<usize> = (0..degree * num_wires + num_virtual_targets).collect();
+    validate_generators(generators, num_wires, degree, &representative_map, inputs)
+}
+
+pub fn validate_generators<F: RichField + Extendable<D>, const D: usize>(
+    generators: &[WitnessGeneratorRef<F, D>],
+    num_wires: usize,
+    degree: usize,
+    representative_map: &[usize],
+    inputs: &[Target],
+) -> GeneratorReport {
+    // Targets are compared through their copy-constraint representative.
+    let representative = |t: Target| representative_map[t.index(num_wires, degree)];
+
+    let mut issues = Vec::new();
+    let mut infos = Vec::with_capacity(generators.len());
+    let mut self_dependent = vec![false; generators.len()];
+    for (index, generator) in generators.iter().enumerate() {
+        let dependencies = generator.0.watch_list();
+
+        let run = catch_unwind(AssertUnwindSafe(|| {
+            let mut witness = PartitionWitness::new(num_wires, degree, representative_map);
+            for &t in dependencies.iter() {
+                witness.set_target(t, F::rand());
+            }
+
+            let mut out_buffer = GeneratedValues::empty();
+            let finished = generator.0.run(&witness, &mut out_buffer);
+            (finished, out_buffer.target_values.into_iter().map(|(t, _)| t).collect::<Vec<_>>())
+        }));
+
+        let outputs = match run {
+            Ok((finished, outputs)) => {
+                if !finished {
+                    issues.push(GeneratorIssue::DidNotRun { generator: index });
+                }
+                outputs
+            }
+            Err(e) => {
+                let message = e
+                    .downcast_ref::<String>()
+                    .cloned()
+                    .or_else(|| e.downcast_ref::<&str>().map(|s| s.to_string()))
+                    .unwrap_or_default();
+                issues.push(GeneratorIssue::Panicked { generator: index, message });
+                Vec::new()
+            }
+        };
+
+        let self_dependencies: Vec<Target> = dependencies
+            .iter()
+            .copied()
+            .filter(|&d| outputs.iter().any(|&o| representative(o) == representative(d)))
+            .collect();
+        if !self_dependencies.is_empty() {
+            self_dependent[index] = true;
+            issues.push(GeneratorIssue::SelfDependency {
+                generator: index,
+                targets: self_dependencies,
+            });
+        }
+
+        infos.push(GeneratorInfo {
+            index,
+            id: generator.0.id(),
+            dependencies,
+            outputs,
+        });
+    }
+
+    // producers and consumers of every representative
+    let mut producers: HashMap<usize, (Target, Vec<usize>)> = HashMap::new();
+    let mut consumers: HashMap<usize, (Target, Vec<usize>)> = HashMap::new();
+    for info in infos.iter() {
+        for &t in info.outputs.iter() {
+            let entry = producers.entry(representative(t)).or_insert((t, Vec::new()));
+            if !entry.1.contains(&info.index) {
+                entry.1.push(info.index);
+            }
+        }
+        for &t in info.dependencies.iter() {
+            let entry = consumers.entry(representative(t)).or_insert((t, Vec::new()));
+            if !entry.1.contains(&info.index) {
+                entry.1.push(info.index);
+            }
+        }
+    }
+
+    let mut multiple: Vec<_> = producers
+        .values()
+        .filter(|(_, p)| p.len() > 1)
+        .map(|(t, p)| GeneratorIssue::MultipleProducers { target: *t, generators: p.clone() })
+        .collect();
+    multiple.sort_by_key(|issue| match issue {
+        GeneratorIssue::MultipleProducers { generators, .. } => generators[0],
+        _ => 0,
+    });
+    issues.extend(multiple);
+
+    let input_representatives: Vec<usize> = inputs.iter().map(|&t| representative(t)).collect();
+    let mut never: Vec<_> = consumers
+        .iter()
+        .filter(|(r, _)| !producers.contains_key(r) && !input_representatives.contains(r))
+        .map(|(_, (t, c))| GeneratorIssue::NeverProduced { target: *t, consumers: c.clone() })
+        .collect();
+    never.sort_by_key(|issue| match issue {
+        GeneratorIssue::NeverProduced { consumers, .. } => consumers[0],
+        _ => 0,
+    });
+    issues.extend(never);
+
+    // Kahn's algorithm on the "writes a dependency of" graph, self loops are reported above.
+    let mut edges = vec![Vec::new(); infos.len()];
+    let mut in_degree = vec![0usize; infos.len()];
+    for (r, (_, c)) in consumers.iter() {
+        if let Some((_, p)) = producers.get(r) {
+            for &from in p.iter() {
+                for &to in c.iter() {
+                    if from != to && !edges[from].contains(&to) {
+                        edges[from].push(to);
+                        in_degree[to] += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    // A self-dependent generator waits for itself, so it never writes its outputs.
+    for (i, &d) in self_dependent.iter().enumerate() {
+        if d {
+            in_degree[i] += 1;
+        }
+    }
+
+    let mut queue: Vec<usize> = (0..infos.len()).filter(|&i| in_degree[i] == 0).collect();
+    while let Some(i) = queue.pop() {
+        for &to in edges[i].iter() {
+            in_degree[to] -= 1;
+            if in_degree[to] == 0 {
+                queue.push(to);
+            }
+        }
+    }
+    // What Kahn leaves are the cycles, the self-dependent generators and everything downstream of them.
+    // The cycles are the strongly connected components with more than one generator.
+    let mut in_cycle = vec![false; infos.len()];
+    for component in strongly_connected_components(&edges) {
+        if component.len() > 1 {
+            for &i in component.iter() {
+                in_cycle[i] = true;
+            }
+            issues.push(GeneratorIssue::Cycle { generators: component });
+        }
+    }
+    let stuck: Vec<usize> = (0..infos.len())
+        .filter(|&i| in_degree[i] > 0 && !in_cycle[i] && !self_dependent[i])
+        .collect();
+    if !stuck.is_empty() {
+        issues.push(GeneratorIssue::Stuck { generators: stuck });
+    }
+
+    GeneratorReport {
+        generators: infos,
+        issues,
+    }
+}
+
+/// Tarjan's algorithm, iterative so that long generator chains do not overflow the stack.
+/// Every component is sorted, and the components are in the order they are completed.
+fn strongly_connected_components(edges: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    let n = edges.len();
+    let mut index = vec![usize::MAX; n];
+    let mut low = vec![0; n];
+    let mut on_stack = vec![false; n];
+    let mut stack = Vec::new();
+    let mut components = Vec::new();
+    let mut next = 0;
+
+    for root in 0..n {
+        if index[root] != usize::MAX {
+            continue;
+        }
+
+        // The nodes being visited and the next of their edges to follow.
+        let mut calls = vec![(root, 0)];
+        index[root] = next;
+        low[root] = next;
+        next += 1;
+        stack.push(root);
+        on_stack[root] = true;
+
+        while let Some((v, e)) = calls.pop() {
+            if e < edges[v].len() {
+                calls.push((v, e + 1));
+                let w = edges[v][e];
+                if index[w] == usize::MAX {
+                    index[w] = next;
+                    low[w] = next;
+                    next += 1;
+                    stack.push(w);
+                    on_stack[w] = true;
+                    calls.push((w, 0));
+                } else if on_stack[w] {
+                    low[v] = low[v].min(index[w]);
+                }
+                continue;
+            }
+
+            if low[v] == index[v] {
+                let mut component = Vec::new();
+                loop {
+                    let w = stack.pop().unwrap();
+                    on_stack[w] = false;
+                    component.push(w);
+                    if w == v {
+                        break;
+                    }
+                }
+                component.sort_unstable();
+                components.push(component);
+            }
+            if let Some(&(parent, _)) = calls.last() {
+                low[parent] = low[parent].min(low[v]);
+            }
+        }
+    }
+
+    components
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use core::marker::PhantomData;
+
+    use plonky2::field::types::Field;
+    use plonky2::gates::gate::Gate;
+    use plonky2::iop::generator::SimpleGenerator;
+    use plonky2::iop::witness::Witness;
+    use plonky2::plonk::circuit_builder::CircuitBuilder;
+    use plonky2::plonk::circuit_data::CommonCircuitData;
+    use plonky2::plonk::config::PoseidonGoldilocksConfig;
+    use plonky2::util::serialization::{Buffer, IoResult};
+
+    use crate::builder::CookBuilderExt;
+    use crate::gates::exp_var_gate::SimpleExpVarGate;
+    use crate::gates::expression_gate::{Expr, Expression, ExpressionGate};
+    use crate::gates::g_w_v_v_constant::SimpleExpConstantGate;
+    use crate::gates::g_w_v_v_low_degree::SimpleExpLowDegreeGate;
+    use crate::gates::gate_with_veriable_vars::SimpleExpTestGate;
+    use crate::gates::simple_add_ext_gate::SimpleMulAddExtensionGate;
+    use crate::gates::simple_add_gate::SimpleMulAddTestGate;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    fn check_gate<G: Gate<F, D>>(gate: G, constants: &[F], config: &CircuitConfig) {
+        let generators = gate.generators(0, constants);
+        let report = validate_generator_list(&generators, config, &[]);
+
+        // The inputs of the gate are never produced inside a single row, everything else must be clean.
+        assert!(
+            report.issues.iter().all(|i| matches!(i, GeneratorIssue::NeverProduced { .. })),
+            "{}: {report}",
+            gate.id()
+        );
+    }
+
+    #[test]
+    fn cook_gates() {
+        let config = CircuitConfig::standard_recursion_config();
+        check_gate(SimpleMulAddTestGate::new_from_config(&config), &[], &config);
+        check_gate(SimpleMulAddExtensionGate::<D>::new_from_config(&config), &[], &config);
+        check_gate(SimpleExpTestGate::new(8, &config), &[], &config);
+        check_gate(SimpleExpConstantGate::new(8, &config), &[F::TWO], &config);
+        check_gate(SimpleExpLowDegreeGate::new(8, &config), &[], &config);
+        check_gate(SimpleExpVarGate::new(8, &config), &[], &config);
+        check_gate(
+            ExpressionGate::new_from_config(
+                Expression::new(&["x", "y"], "out", &["c"], Expr::constant("c") * Expr::wire("x") + Expr::wire("y")),
+                &config,
+            ),
+            &[F::TWO],
+            &config,
+        );
+    }
+
+    #[test]
+    fn cook_circuit() {
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config.clone());
+
+        let inputs: Vec<Target> = (0..5).map(|_| builder.add_virtual_target()).collect();
+        let sum = builder.mul_add3(inputs[0], inputs[1], inputs[2], inputs[3], inputs[4]);
+        let exponent_bits = builder.split_le(inputs[1], 64);
+        let power = builder.exp_var(sum, &exponent_bits);
+        let constant_power = builder.exp_constant_base(F::TWO, 100);
+        let out = builder.mul(power, constant_power);
+        builder.register_public_input(out);
+
+        let data = builder.build::<C>();
+        let report = validate_circuit_generators(&data, &inputs);
+        assert!(report.is_ok(), "{report}");
+    }
+
+    // A generator which waits for the wire it writes, like `SimpleExpTestGenerator` used to.
+    #[derive(Debug, Default)]
+    struct SelfDependentGenerator {
+        _phantom: PhantomData<F>,
+    }
+
+    impl SimpleGenerator<F, D> for SelfDependentGenerator {
+        fn id(&self) -> String {
+            "SelfDependentGenerator".to_string()
+        }
+
+        fn dependencies(&self) -> Vec<Target> {
+            vec![Target::wire(0, 0), Target::wire(0, 1)]
+        }
+
+        fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+            let x = witness.get_target(Target::wire(0, 0));
+            out_buffer.set_target(Target::wire(0, 1), x.square());
+        }
+
+        fn serialize(&self, _dst: &mut Vec<u8>, _common_data: &CommonCircuitData<F, D>) -> IoResult<()> {
+            Ok(())
+        }
+
+        fn deserialize(_src: &mut Buffer, _common_data: &CommonCircuitData<F, D>) -> IoResult<Self> {
+            Ok(Self::default())
+        }
+    }
+
+    // A generator which reads back a wire it only wrote to `out_buffer`, like `SimpleExpConstantGenerator` used to.
+    #[derive(Debug, Default)]
+    struct ReadAfterWriteGenerator {
+        _phantom: PhantomData<F>,
+    }
+
+    impl SimpleGenerator<F, D> for ReadAfterWriteGenerator {
+        fn id(&self) -> String {
+            "ReadAfterWriteGenerator".to_string()
+        }
+
+        fn dependencies(&self) -> Vec<Target> {
+            vec![]
+        }
+
+        fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+            out_buffer.set_target(Target::wire(0, 2), F::TWO);
+            let written = witness.get_target(Target::wire(0, 2));
+            out_buffer.set_target(Target::wire(0, 3), written.square());
+        }
+
+        fn serialize(&self, _dst: &mut Vec<u8>, _common_data: &CommonCircuitData<F, D>) -> IoResult<()> {
+            Ok(())
+        }
+
+        fn deserialize(_src: &mut Buffer, _common_data: &CommonCircuitData<F, D>) -> IoResult<Self> {
+            Ok(Self::default())
+        }
+    }
+
+    // A generator which only reads the output of `SelfDependentGenerator`.
+    #[derive(Debug, Default)]
+    struct DownstreamGenerator {
+        _phantom: PhantomData<F>,
+    }
+
+    impl SimpleGenerator<F, D> for DownstreamGenerator {
+        fn id(&self) -> String {
+            "DownstreamGenerator".to_string()
+        }
+
+        fn dependencies(&self) -> Vec<Target> {
+            vec![Target::wire(0, 1)]
+        }
+
+        fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+            let x = witness.get_target(Target::wire(0, 1));
+            out_buffer.set_target(Target::wire(0, 4), x + F::ONE);
+        }
+
+        fn serialize(&self, _dst: &mut Vec<u8>, _common_data: &CommonCircuitData<F, D>) -> IoResult<()> {
+            Ok(())
+        }
+
+        fn deserialize(_src: &mut Buffer, _common_data: &CommonCircuitData<F, D>) -> IoResult<Self> {
+            Ok(Self::default())
+        }
+    }
+
+    #[test]
+    fn catches_broken_generators() {
+        let config = CircuitConfig::standard_recursion_config();
+        let generators = vec![
+            WitnessGeneratorRef::new(SelfDependentGenerator::default().adapter()),
+            WitnessGeneratorRef::new(ReadAfterWriteGenerator::default().adapter()),
+            WitnessGeneratorRef::new(SelfDependentGenerator::default().adapter()),
+            WitnessGeneratorRef::new(DownstreamGenerator::default().adapter()),
+        ];
+
+        let report = validate_generator_list(&generators, &config, &[Target::wire(0, 0)]);
+
+        assert!(report
+            .issues
+            .iter()
+            .any(|i| matches!(i, GeneratorIssue::SelfDependency { generator: 0, .. })));
+        assert!(report
+            .issues
+            .iter()
+            .any(|i| matches!(i, GeneratorIssue::Panicked { generator: 1, .. })));
+        assert!(report.issues.iter().any(|i| matches!(
+            i,
+            GeneratorIssue::MultipleProducers { generators, .. } if generators == &vec![0, 2]
+        )));
+        // Both copies wait for each other's output, and only they form the cycle.
+        let cycles: Vec<&Vec<usize>> = report
+            .issues
+            .iter()
+            .filter_map(|i| match i {
+                GeneratorIssue::Cycle { generators } => Some(generators),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(cycles, [&vec![0, 2]]);
+        assert!(report
+            .issues
+            .iter()
+            .any(|i| matches!(i, GeneratorIssue::Stuck { generators } if generators == &vec![3])));
+    }
+
+    // `SimpleExpTestGenerator` with its original dependencies: the base and the powers it writes itself.
+    #[derive(Debug)]
+    struct OriginalExpTestGenerator {
+        gate: SimpleExpTestGate,
+    }
+
+    impl SimpleGenerator<F, D> for OriginalExpTestGenerator {
+        fn id(&self) -> String {
+            "OriginalExpTestGenerator".to_string()
+        }
+
+        fn dependencies(&self) -> Vec<Target> {
+            let mut deps = vec![Target::wire(0, self.gate.wire_base())];
+            for i in 1..(self.gate.num_limbs + 1) {
+                deps.push(Target::wire(0, i));
+            }
+            deps
+        }
+
+        fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+            let base = witness.get_target(Target::wire(0, self.gate.wire_base()));
+            let mut power = F::ONE;
+            for i in 1..(self.gate.num_limbs + 1) {
+                power *= base;
+                out_buffer.set_target(Target::wire(0, i), power);
+            }
+            out_buffer.set_target(Target::wire(0, self.gate.wire_output()), power);
+        }
+
+        fn serialize(&self, _dst: &mut Vec<u8>, _common_data: &CommonCircuitData<F, D>) -> IoResult<()> {
+            Ok(())
+        }
+
+        fn deserialize(_src: &mut Buffer, _common_data: &CommonCircuitData<F, D>) -> IoResult<Self> {
+            Err(plonky2::util::serialization::IoError)
+        }
+    }
+
+    #[test]
+    fn downstream_of_self_dependency() {
+        let config = CircuitConfig::standard_recursion_config();
+        let gate = SimpleExpTestGate::new(4, &config);
+        // The downstream generator reads wire 1, the first power.
+        let generators = vec![
+            WitnessGeneratorRef::new(OriginalExpTestGenerator { gate: gate.clone() }.adapter()),
+            WitnessGeneratorRef::new(DownstreamGenerator::default().adapter()),
+        ];
+
+        let report = validate_generator_list(&generators, &config, &[Target::wire(0, gate.wire_base())]);
+
+        assert!(report
+            .issues
+            .iter()
+            .any(|i| matches!(i, GeneratorIssue::SelfDependency { generator: 0, .. })));
+        assert!(!report.issues.iter().any(|i| matches!(i, GeneratorIssue::Cycle { .. })));
+        assert!(report
+            .issues
+            .iter()
+            .any(|i| matches!(i, GeneratorIssue::Stuck { generators } if generators == &vec![1])));
+    }
+
+    #[test]
+    fn components() {
+        // 0 -> 1 -> 2 -> 0 is a cycle, 3 is downstream of it and 4 -> 5 -> 4 is a second one.
+        let edges = vec![vec![1], vec![2], vec![0, 3], vec![4], vec![5], vec![4]];
+        let mut cycles: Vec<Vec<usize>> = strongly_connected_components(&edges)
+            .into_iter()
+            .filter(|c| c.len() > 1)
+            .collect();
+        cycles.sort();
+        assert_eq!(cycles, vec![vec![0, 1, 2], vec![4, 5]]);
+    }
+}
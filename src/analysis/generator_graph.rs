@@ -0,0 +1,291 @@
+//! The witness generation flow of a built circuit as a Graphviz DOT graph.
+//!
+//! Every node is a generator, and an edge `a -> b` means `a` writes a dependency of `b`, compared through
+//! copy-constraint partitions. The generators are run in rounds from the inputs like plonky2 does:
+//! a node's depth is the round it runs in, and generators which never run are stuck.
+
+use core::fmt::Write;
+use std::collections::HashMap;
+
+use plonky2::field::extension::Extendable;
+use plonky2::hash::hash_types::RichField;
+use plonky2::iop::target::Target;
+use plonky2::plonk::circuit_data::CircuitData;
+use plonky2::plonk::config::GenericConfig;
+
+use crate::analysis::generator_deps::validate_circuit_generators;
+use crate::analysis::mock_prover::row_gates;
+
+#[derive(Debug, Clone)]
+pub struct GeneratorNode {
+    pub id: String,
+    /// The row of the first wire the generator writes, or else reads.
+    pub row: Option<usize>,
+    /// The `id()` of the gate on `row`.
+    pub gate: Option<String>,
+    /// The round the generator runs in, starting at 1, `None` if it is stuck.
+    pub depth: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct GeneratorGraph {
+    pub nodes: Vec<GeneratorNode>,
+    /// `(from, to)`: `from` writes a dependency of `to`.
+    pub edges: Vec<(usize, usize)>,
+}
+
+/// Builds the generator graph of `data`, `inputs` are the targets set by the caller's `PartialWitness`.
+pub fn generator_graph<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    data: &CircuitData<F, C, D>,
+    inputs: &[Target],
+) -> GeneratorGraph {
+    let (num_wires, degree) = (data.common.config.num_wires, data.common.degree());
+    let representative = |t: Target| data.prover_only.representative_map[t.index(num_wires, degree)];
+    let report = validate_circuit_generators(data, inputs);
+    let gates = row_gates(data);
+
+    let mut producers: HashMap<usize, Vec<usize>> = HashMap::new();
+    for info in report.generators.iter() {
+        for &t in info.outputs.iter() {
+            let p = producers.entry(representative(t)).or_default();
+            if !p.contains(&info.index) {
+                p.push(info.index);
+            }
+        }
+    }
+
+    let mut edges = Vec::new();
+    for info in report.generators.iter() {
+        for &t in info.dependencies.iter() {
+            for &from in producers.get(&representative(t)).into_iter().flatten() {
+                if from != info.index && !edges.contains(&(from, info.index)) {
+                    edges.push((from, info.index));
+                }
+            }
+        }
+    }
+
+    // The round every partition is known in, inputs are known before the first round.
+    let mut known: HashMap<usize, usize> = inputs.iter().map(|&t| (representative(t), 0)).collect();
+    let mut depths = vec![None; report.generators.len()];
+    let mut round = 0;
+    loop {
+        round += 1;
+        let ready: Vec<usize> = report
+            .generators
+            .iter()
+            .filter(|info| depths[info.index].is_none())
+            .filter(|info| info.dependencies.iter().all(|&t| known.contains_key(&representative(t))))
+            .map(|info| info.index)
+            .collect();
+        if ready.is_empty() {
+            break;
+        }
+        for &i in ready.iter() {
+            depths[i] = Some(round);
+            for &t in report.generators[i].outputs.iter() {
+                known.entry(representative(t)).or_insert(round);
+            }
+        }
+    }
+
+    let nodes = report
+        .generators
+        .iter()
+        .map(|info| {
+            let row = info
+                .outputs
+                .iter()
+                .chain(info.dependencies.iter())
+                .find_map(|t| match t {
+                    Target::Wire(w) => Some(w.row),
+                    Target::VirtualTarget { .. } => None,
+                });
+            GeneratorNode {
+                id: info.id.clone(),
+                row,
+                gate: row.map(|r| data.common.gates[gates[r]].0.id()),
+                depth: depths[info.index],
+            }
+        })
+        .collect();
+
+    GeneratorGraph { nodes, edges }
+}
+
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+impl GeneratorGraph {
+    pub fn stuck(&self) -> Vec<usize> {
+        (0..self.nodes.len()).filter(|&i| self.nodes[i].depth.is_none()).collect()
+    }
+
+    /// The longest chain of generators each waiting for the previous one, from an input to the deepest node.
+    pub fn longest_chain(&self) -> Vec<usize> {
+        let deepest = (0..self.nodes.len()).filter(|&i| self.nodes[i].depth.is_some()).max_by_key(|&i| self.nodes[i].depth);
+        let Some(mut last) = deepest else {
+            return Vec::new();
+        };
+
+        let mut chain = vec![last];
+        while let Some(&(from, _)) = self
+            .edges
+            .iter()
+            .find(|&&(from, to)| to == last && self.nodes[from].depth.map(|d| d + 1) == self.nodes[last].depth)
+        {
+            chain.push(from);
+            last = from;
+        }
+        chain.reverse();
+        chain
+    }
+
+    /// One node per generator labelled with its `id()`, row and depth.
+    /// Stuck generators are red and the longest chain is bold.
+    pub fn to_dot(&self) -> String {
+        let chain = self.longest_chain();
+        let on_chain = |from: usize, to: usize| chain.windows(2).any(|w| w[0] == from && w[1] == to);
+
+        let mut dot = String::from("digraph generators {\n    rankdir=LR;\n    node [shape=box];\n");
+        for (i, node) in self.nodes.iter().enumerate() {
+            let row = node.row.map_or("-".to_string(), |r| r.to_string());
+            let depth = node.depth.map_or("stuck".to_string(), |d| format!("depth {d}"));
+            let color = if node.depth.is_none() { ", color=red" } else { "" };
+            let label = format!("{}\\nrow {row}, {depth}", escape(&node.id));
+            writeln!(dot, "    g{i} [label=\"{label}\"{color}];").unwrap();
+        }
+        for &(from, to) in self.edges.iter() {
+            let style = if on_chain(from, to) { " [penwidth=3]" } else { "" };
+            writeln!(dot, "    g{from} -> g{to}{style};").unwrap();
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// One node per gate type, or per generator `id()` for generators without a row,
+    /// labelled with the number of generators and their depths. Edges are labelled with their count.
+    pub fn to_collapsed_dot(&self) -> String {
+        let mut groups: Vec<String> = Vec::new();
+        let group_of: Vec<usize> = self
+            .nodes
+            .iter()
+            .map(|node| {
+                let key = node.gate.clone().unwrap_or_else(|| node.id.clone());
+                groups.iter().position(|g| *g == key).unwrap_or_else(|| {
+                    groups.push(key);
+                    groups.len() - 1
+                })
+            })
+            .collect();
+
+        let mut dot = String::from("digraph generators {\n    rankdir=LR;\n    node [shape=box];\n");
+        for (g, key) in groups.iter().enumerate() {
+            let members: Vec<&GeneratorNode> = (0..self.nodes.len())
+                .filter(|&i| group_of[i] == g)
+                .map(|i| &self.nodes[i])
+                .collect();
+            let depths: Vec<usize> = members.iter().filter_map(|n| n.depth).collect();
+            let stuck = members.len() - depths.len();
+            let depth = match (depths.iter().min(), depths.iter().max()) {
+                (Some(min), Some(max)) => format!("depth {min}..{max}"),
+                _ => "no depth".to_string(),
+            };
+            let (stuck, color) = if stuck > 0 {
+                (format!(", {stuck} stuck"), ", color=red")
+            } else {
+                (String::new(), "")
+            };
+            writeln!(
+                dot,
+                "    g{g} [label=\"{}\\n{} generators, {depth}{stuck}\"{color}];",
+                escape(key),
+                members.len()
+            )
+            .unwrap();
+        }
+
+        let mut edges: Vec<((usize, usize), usize)> = Vec::new();
+        for &(from, to) in self.edges.iter() {
+            let edge = (group_of[from], group_of[to]);
+            if edge.0 == edge.1 {
+                continue;
+            }
+            match edges.iter_mut().find(|(e, _)| *e == edge) {
+                Some((_, count)) => *count += 1,
+                None => edges.push((edge, 1)),
+            }
+        }
+        for ((from, to), count) in edges {
+            writeln!(dot, "    g{from} -> g{to} [label=\"{count}\"];").unwrap();
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use plonky2::field::types::Field;
+    use plonky2::plonk::circuit_builder::CircuitBuilder;
+    use plonky2::plonk::circuit_data::CircuitConfig;
+    use plonky2::plonk::config::PoseidonGoldilocksConfig;
+
+    use crate::builder::CookBuilderExt;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    fn circuit() -> (CircuitData<F, C, D>, Vec<Target>) {
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let inputs: Vec<Target> = (0..5).map(|_| builder.add_virtual_target()).collect();
+        let sum = builder.mul_add3(inputs[0], inputs[1], inputs[2], inputs[3], inputs[4]);
+        let squared = builder.mul(sum, sum);
+        let power = builder.exp_constant_base(F::TWO, 10);
+        let out = builder.mul(squared, power);
+        builder.register_public_input(out);
+        (builder.build::<C>(), inputs)
+    }
+
+    #[test]
+    fn chain() {
+        let (data, inputs) = circuit();
+        let graph = generator_graph(&data, &inputs);
+
+        assert!(graph.stuck().is_empty());
+        // `mul_add3`, `sum * sum` and the final product wait for each other.
+        let chain = graph.longest_chain();
+        assert_eq!(chain.len(), 3);
+        for (i, &node) in chain.iter().enumerate() {
+            assert_eq!(graph.nodes[node].depth, Some(i + 1));
+        }
+        for w in chain.windows(2) {
+            assert!(graph.edges.contains(&(w[0], w[1])));
+        }
+    }
+
+    #[test]
+    fn stuck_without_inputs() {
+        let (data, inputs) = circuit();
+        let graph = generator_graph(&data, &inputs[1..]);
+
+        let stuck = graph.stuck();
+        assert!(stuck.iter().any(|&i| graph.nodes[i].id.starts_with("SimpleMulAddTestGenerator")));
+        assert!(graph.to_dot().contains("color=red"));
+    }
+
+    #[test]
+    fn collapsed() {
+        let (data, inputs) = circuit();
+        let graph = generator_graph(&data, &inputs);
+        let dot = graph.to_collapsed_dot();
+
+        assert!(dot.contains("SimpleMulAddTestGate"));
+        assert!(dot.contains("SimpleExpConstantGate"));
+        assert!(dot.lines().filter(|l| l.contains(" generators, ")).count() < graph.nodes.len());
+    }
+}
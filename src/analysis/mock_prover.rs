@@ -0,0 +1,395 @@
+use core::fmt;
+use std::collections::HashMap;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use anyhow::{anyhow, Result};
+
+use plonky2::field::extension::Extendable;
+use plonky2::field::polynomial::PolynomialValues;
+use plonky2::field::types::Field;
+use plonky2::hash::hash_types::RichField;
+use plonky2::iop::generator::generate_partial_witness;
+use plonky2::iop::target::Target;
+use plonky2::iop::witness::{PartialWitness, Witness};
+use plonky2::plonk::circuit_data::CircuitData;
+use plonky2::plonk::config::{GenericConfig, Hasher};
+use plonky2::plonk::vars::EvaluationVars;
+
+/// A gate constraint which does not vanish on a row.
+#[derive(Debug, Clone)]
+pub struct ConstraintViolation<F: RichField + Extendable<D>, const D: usize> {
+    pub row: usize,
+    pub gate: String,
+    pub constraint_index: usize,
+    pub value: F::Extension,
+    /// The values of the wires used by the gate on this row.
+    pub wires: Vec<F>,
+}
+
+/// A wire whose value differs from the first wire, in row then column order, of its copy-constraint partition.
+#[derive(Debug, Clone)]
+pub struct CopyViolation<F: Field> {
+    pub row: usize,
+    pub column: usize,
+    pub value: F,
+    pub first_row: usize,
+    pub first_column: usize,
+    pub first_value: F,
+}
+
+#[derive(Debug, Clone)]
+pub struct MockProveReport<F: RichField + Extendable<D>, const D: usize> {
+    pub constraint_violations: Vec<ConstraintViolation<F, D>>,
+    pub copy_violations: Vec<CopyViolation<F>>,
+    /// Indices of the public inputs which were never set by the witness.
+    pub unset_public_inputs: Vec<usize>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> MockProveReport<F, D> {
+    pub fn is_ok(&self) -> bool {
+        self.constraint_violations.is_empty()
+            && self.copy_violations.is_empty()
+            && self.unset_public_inputs.is_empty()
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> fmt::Display for MockProveReport<F, D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_ok() {
+            return writeln!(f, "all constraints are satisfied");
+        }
+
+        for v in self.constraint_violations.iter() {
+            writeln!(
+                f,
+                "row {}, gate {}: constraint {} evaluates to {:?}, wires {:?}",
+                v.row, v.gate, v.constraint_index, v.value, v.wires
+            )?;
+        }
+        for v in self.copy_violations.iter() {
+            writeln!(
+                f,
+                "copy constraint: wire ({}, {}) = {} but the wire ({}, {}) it is copied from = {}",
+                v.row, v.column, v.value, v.first_row, v.first_column, v.first_value
+            )?;
+        }
+        for i in self.unset_public_inputs.iter() {
+            writeln!(f, "public input {} is not set", i)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Checks a witness against every gate constraint, copy constraint and public input of a built circuit,
+/// without committing to anything or running FRI.
+pub struct MockProver<'a, F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> {
+    pub data: &'a CircuitData<F, C, D>,
+    /// `wires[row][column]`, unset wires are zero like in plonky2's `full_witness`.
+    pub wires: Vec<Vec<F>>,
+    /// The value of every public input, `None` if it was never set.
+    pub public_inputs: Vec<Option<F>>,
+}
+
+impl<'a, F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> MockProver<'a, F, C, D> {
+    /// Runs the witness generation of `data` on `inputs`.
+    /// A panic of a generator, e.g. a target set twice with different values, is returned as an error.
+    pub fn new(data: &'a CircuitData<F, C, D>, inputs: PartialWitness<F>) -> Result<Self> {
+        let witness = catch_unwind(AssertUnwindSafe(|| {
+            generate_partial_witness(inputs, &data.prover_only, &data.common)
+        }))
+        .map_err(|e| {
+            let msg = e
+                .downcast_ref::<String>()
+                .cloned()
+                .or_else(|| e.downcast_ref::<&str>().map(|s| s.to_string()))
+                .unwrap_or_default();
+            anyhow!("witness generation failed: {msg}")
+        })?;
+
+        let num_wires = data.common.config.num_wires;
+        let wires = (0..data.common.degree())
+            .map(|row| {
+                (0..num_wires)
+                    .map(|column| witness.try_get_target(Target::wire(row, column)).unwrap_or(F::ZERO))
+                    .collect()
+            })
+            .collect();
+
+        let public_inputs = data
+            .prover_only
+            .public_inputs
+            .iter()
+            .map(|&t| witness.try_get_target(t))
+            .collect();
+
+        Ok(Self {
+            data,
+            wires,
+            public_inputs,
+        })
+    }
+
+    pub fn wire(&self, row: usize, column: usize) -> F {
+        self.wires[row][column]
+    }
+
+    /// Overwrites a single wire, which is handy to check that a gate really catches a wrong value.
+    pub fn set_wire(&mut self, row: usize, column: usize, value: F) {
+        self.wires[row][column] = value;
+    }
+
+    /// The index of the gate in `common.gates` used by every row.
+    pub fn row_gates(&self) -> Vec<usize> {
+        row_gates(self.data)
+    }
+
+    pub fn check(&self) -> MockProveReport<F, D> {
+        let common = &self.data.common;
+        let num_wires = common.config.num_wires;
+        let degree = common.degree();
+
+        let unset_public_inputs: Vec<usize> = self
+            .public_inputs
+            .iter()
+            .enumerate()
+            .filter(|(_, v)| v.is_none())
+            .map(|(i, _)| i)
+            .collect();
+        let public_inputs: Vec<F> = self.public_inputs.iter().map(|v| v.unwrap_or(F::ZERO)).collect();
+        let public_inputs_hash = C::InnerHasher::hash_no_pad(&public_inputs);
+
+        let gate_constants = gate_constant_values(self.data);
+
+        let mut constraint_violations = Vec::new();
+        for (row, gate_index) in self.row_gates().into_iter().enumerate() {
+            let gate = &common.gates[gate_index];
+
+            let local_wires: Vec<F::Extension> = self.wires[row].iter().map(|&w| w.into()).collect();
+            let local_constants: Vec<F::Extension> =
+                gate_constants.iter().map(|c| c.values[row].into()).collect();
+
+            let constraints = gate.0.eval_unfiltered(EvaluationVars {
+                local_constants: &local_constants,
+                local_wires: &local_wires,
+                public_inputs_hash: &public_inputs_hash,
+            });
+
+            for (constraint_index, value) in constraints.into_iter().enumerate() {
+                if value != F::Extension::ZERO {
+                    constraint_violations.push(ConstraintViolation {
+                        row,
+                        gate: gate.0.id(),
+                        constraint_index,
+                        value,
+                        wires: self.wires[row][..gate.0.num_wires()].to_vec(),
+                    });
+                }
+            }
+        }
+
+        // The representative of a partition is often a virtual target, which has no value in `wires`,
+        // so the wires are compared with the first wire of their partition instead.
+        let mut copy_violations = Vec::new();
+        let mut first_wires: HashMap<usize, (usize, usize)> = HashMap::new();
+        let representative_map = &self.data.prover_only.representative_map;
+        for row in 0..degree {
+            for column in 0..common.config.num_routed_wires {
+                let representative = representative_map[Target::wire(row, column).index(num_wires, degree)];
+                let (first_row, first_column) = *first_wires.entry(representative).or_insert((row, column));
+
+                let value = self.wires[row][column];
+                let first_value = self.wires[first_row][first_column];
+                if value != first_value {
+                    copy_violations.push(CopyViolation {
+                        row,
+                        column,
+                        value,
+                        first_row,
+                        first_column,
+                        first_value,
+                    });
+                }
+            }
+        }
+
+        MockProveReport {
+            constraint_violations,
+            copy_violations,
+            unset_public_inputs,
+        }
+    }
+}
+
+/// The index of the gate in `common.gates` used by every row, read back from the selector polynomials.
+pub fn row_gates<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    data: &CircuitData<F, C, D>,
+) -> Vec<usize> {
+    let common = &data.common;
+    let selectors = constant_values(data, 0..common.selectors_info.num_selectors());
+
+    (0..common.degree())
+        .map(|row| {
+            (0..common.gates.len())
+                .find(|&gate_index| {
+                    let selector = common.selectors_info.selector_indices[gate_index];
+                    selectors[selector].values[row] == F::from_canonical_usize(gate_index)
+                })
+                .unwrap_or_else(|| panic!("the selectors of row {row} do not match any gate"))
+        })
+        .collect()
+}
+
+/// The values over the rows of the gate constants, which come after the selectors and the lookup selectors.
+pub fn gate_constant_values<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    data: &CircuitData<F, C, D>,
+) -> Vec<PolynomialValues<F>> {
+    let common = &data.common;
+    let offset = common.selectors_info.num_selectors() + common.num_lookup_selectors;
+    constant_values(data, offset..common.num_constants)
+}
+
+/// The values over the rows of the constant polynomials in `range`.
+fn constant_values<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    data: &CircuitData<F, C, D>,
+    range: core::ops::Range<usize>,
+) -> Vec<PolynomialValues<F>> {
+    data.prover_only.constants_sigmas_commitment.polynomials[range]
+        .iter()
+        .map(|p| p.clone().fft())
+        .collect()
+}
+
+/// Runs witness generation on `inputs` and checks the result against every constraint of `data`.
+pub fn mock_prove<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    data: &CircuitData<F, C, D>,
+    inputs: PartialWitness<F>,
+) -> Result<MockProveReport<F, D>> {
+    Ok(MockProver::new(data, inputs)?.check())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use plonky2::field::types::Sample;
+    use plonky2::iop::witness::WitnessWrite;
+    use plonky2::plonk::circuit_builder::CircuitBuilder;
+    use plonky2::plonk::circuit_data::CircuitConfig;
+    use plonky2::plonk::config::PoseidonGoldilocksConfig;
+
+    use crate::builder::CookBuilderExt;
+    use crate::gates::simple_add_gate::SimpleMulAddTestGate;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    fn mul_add_circuit() -> (CircuitData<F, C, D>, Vec<Target>, Target) {
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let targets: Vec<Target> = (0..5).map(|_| builder.add_virtual_target()).collect();
+        let output = builder.mul_add3(targets[0], targets[1], targets[2], targets[3], targets[4]);
+        builder.register_public_input(output);
+
+        (builder.build::<C>(), targets, output)
+    }
+
+    #[test]
+    fn good_witness() -> Result<()> {
+        let (data, targets, _) = mul_add_circuit();
+
+        let mut pw = PartialWitness::new();
+        for &t in targets.iter() {
+            pw.set_target(t, F::rand());
+        }
+
+        let report = mock_prove(&data, pw)?;
+        assert!(report.is_ok(), "{report}");
+        Ok(())
+    }
+
+    #[test]
+    fn conflicting_witness() {
+        let (data, targets, output) = mul_add_circuit();
+
+        let mut pw = PartialWitness::new();
+        for &t in targets.iter() {
+            pw.set_target(t, F::ONE);
+        }
+        pw.set_target(output, F::ONE);
+
+        assert!(mock_prove(&data, pw).is_err());
+    }
+
+    #[test]
+    fn tampered_wire() -> Result<()> {
+        let (data, targets, output) = mul_add_circuit();
+
+        let mut pw = PartialWitness::new();
+        for &t in targets.iter() {
+            pw.set_target(t, F::rand());
+        }
+
+        let mut prover = MockProver::new(&data, pw)?;
+        let (row, column) = match output {
+            Target::Wire(w) => (w.row, w.column),
+            _ => unreachable!(),
+        };
+        prover.set_wire(row, column, prover.wire(row, column) + F::ONE);
+
+        let report = prover.check();
+        assert!(!report.is_ok());
+
+        let violation = &report.constraint_violations[0];
+        assert_eq!(violation.row, row);
+        // The cook gates use their `Debug` output as `id()`
+        assert_eq!(
+            violation.gate,
+            format!("{:?}", SimpleMulAddTestGate::new_from_config(&data.common.config))
+        );
+        assert_eq!(violation.constraint_index, 0);
+        assert_eq!(violation.wires[column], prover.wire(row, column));
+
+        Ok(())
+    }
+
+    #[test]
+    fn tampered_copy() -> Result<()> {
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        // `x` is copied into both multiplicands, the virtual target may be the representative of their partition.
+        let x = builder.add_virtual_target();
+        let others: Vec<Target> = (0..3).map(|_| builder.add_virtual_target()).collect();
+        let output = builder.mul_add3(x, x, others[0], others[1], others[2]);
+        builder.register_public_input(output);
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        for &t in others.iter().chain([&x]) {
+            pw.set_target(t, F::rand());
+        }
+
+        let mut prover = MockProver::new(&data, pw)?;
+        let row = match output {
+            Target::Wire(w) => w.row,
+            _ => unreachable!(),
+        };
+        let (first, second) = (
+            SimpleMulAddTestGate::wire_ith_multiplicand_0(0),
+            SimpleMulAddTestGate::wire_ith_multiplicand_1(0),
+        );
+        prover.set_wire(row, second, prover.wire(row, second) + F::ONE);
+
+        let report = prover.check();
+        assert_eq!(report.copy_violations.len(), 1);
+        let violation = &report.copy_violations[0];
+        assert_eq!((violation.row, violation.column), (row, second));
+        assert_eq!((violation.first_row, violation.first_column), (row, first));
+        assert_eq!(violation.value, violation.first_value + F::ONE);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,11 @@
+pub mod degree;
+pub mod diff;
+pub mod estimate;
+pub mod generator_deps;
+pub mod generator_graph;
+pub mod mock_prover;
+pub mod profile;
+pub mod stats;
+pub mod symbolic;
+pub mod trace;
+pub mod under_constrained;
@@ -0,0 +1,395 @@
+//! Where `prove` spends its time and memory.
+//!
+//! `prove_with_report` runs plonky2's `prove` with a `TimingTree` and groups its scopes into the phases of the
+//! prover: witness generation, wire commitment, permutation argument, quotient and FRI. The tree is only
+//! readable through the lines `TimingTree::print` logs, and capturing them needs the logger, which belongs
+//! to the application: the caller passes a function returning those lines, see `cook prove --report`.
+//! Without them, e.g. when plonky2 is built without its `timing` feature, the report only has the total
+//! time and memory.
+//!
+//! Memory is the resident set size of the process, sampled every few milliseconds from `/proc/self/status`,
+//! so it is `None` outside of Linux.
+
+use core::fmt;
+use core::time::Duration;
+use std::fs;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Instant;
+
+use anyhow::Result;
+use log::Level;
+use serde_json::{json, Value};
+
+use plonky2::field::extension::Extendable;
+use plonky2::hash::hash_types::RichField;
+use plonky2::iop::witness::PartialWitness;
+use plonky2::plonk::circuit_data::CircuitData;
+use plonky2::plonk::config::GenericConfig;
+use plonky2::plonk::proof::ProofWithPublicInputs;
+use plonky2::plonk::prover::prove;
+use plonky2::util::timing::TimingTree;
+
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    WitnessGeneration,
+    WireCommitment,
+    PermutationArgument,
+    Quotient,
+    Fri,
+    Other,
+}
+
+impl Phase {
+    pub const ALL: [Phase; 6] = [
+        Phase::WitnessGeneration,
+        Phase::WireCommitment,
+        Phase::PermutationArgument,
+        Phase::Quotient,
+        Phase::Fri,
+        Phase::Other,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Phase::WitnessGeneration => "witness generation",
+            Phase::WireCommitment => "wire commitment",
+            Phase::PermutationArgument => "permutation argument",
+            Phase::Quotient => "quotient",
+            Phase::Fri => "fri",
+            Phase::Other => "other",
+        }
+    }
+
+    /// The phase of a top level scope of plonky2's `prove`, from its name.
+    fn of_scope(name: &str) -> Phase {
+        let name = name.to_lowercase();
+        if name.contains("generator") || name.contains("witness") {
+            Phase::WitnessGeneration
+        } else if name.contains("wire") {
+            Phase::WireCommitment
+        } else if name.contains("opening") || name.contains("fri") {
+            // Before the permutation argument, for "construct the opening set, including lookups".
+            Phase::Fri
+        } else if name.contains("partial product") || name.contains("z's") || name.contains("lookup") {
+            Phase::PermutationArgument
+        } else if name.contains("quotient") {
+            Phase::Quotient
+        } else {
+            Phase::Other
+        }
+    }
+}
+
+/// A scope of the `TimingTree`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scope {
+    pub name: String,
+    pub duration: Duration,
+    pub children: Vec<Scope>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PhaseReport {
+    pub phase: Phase,
+    pub duration: Duration,
+    /// The highest resident set size sampled during the phase, in bytes.
+    pub peak_memory: Option<u64>,
+    /// The resident set size at the end of the phase minus at its start, in bytes.
+    pub memory_delta: Option<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProvingReport {
+    pub total: Duration,
+    pub peak_memory: Option<u64>,
+    /// The phases in prover order, without the ones with no scope.
+    pub phases: Vec<PhaseReport>,
+    /// The scopes of plonky2's `prove`, empty when they could not be captured.
+    pub scopes: Vec<Scope>,
+}
+
+impl ProvingReport {
+    pub fn phase(&self, phase: Phase) -> Option<&PhaseReport> {
+        self.phases.iter().find(|p| p.phase == phase)
+    }
+
+    pub fn to_json(&self) -> String {
+        fn scope_json(scope: &Scope) -> Value {
+            json!({
+                "name": scope.name,
+                "seconds": scope.duration.as_secs_f64(),
+                "children": scope.children.iter().map(scope_json).collect::<Vec<_>>(),
+            })
+        }
+
+        let phases: Vec<Value> = self
+            .phases
+            .iter()
+            .map(|p| {
+                json!({
+                    "phase": p.phase.name(),
+                    "seconds": p.duration.as_secs_f64(),
+                    "peak_memory_bytes": p.peak_memory,
+                    "memory_delta_bytes": p.memory_delta,
+                })
+            })
+            .collect();
+        let value = json!({
+            "total_seconds": self.total.as_secs_f64(),
+            "peak_memory_bytes": self.peak_memory,
+            "phases": phases,
+            "scopes": self.scopes.iter().map(scope_json).collect::<Vec<_>>(),
+        });
+        serde_json::to_string_pretty(&value).expect("a JSON value is always serializable")
+    }
+}
+
+fn megabytes(bytes: u64) -> String {
+    format!("{:.1} MB", bytes as f64 / (1 << 20) as f64)
+}
+
+impl fmt::Display for ProvingReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "total: {:.4}s", self.total.as_secs_f64())?;
+        if let Some(peak) = self.peak_memory {
+            writeln!(f, "peak memory: {}", megabytes(peak))?;
+        }
+        if self.phases.is_empty() {
+            writeln!(f, "no phase breakdown, the timing tree was not captured")?;
+        }
+        for p in self.phases.iter() {
+            let share = 100.0 * p.duration.as_secs_f64() / self.total.as_secs_f64().max(f64::MIN_POSITIVE);
+            write!(f, "{:<22} {:>9.4}s {:>5.1}%", p.phase.name(), p.duration.as_secs_f64(), share)?;
+            if let Some(peak) = p.peak_memory {
+                write!(f, "   peak {}", megabytes(peak))?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Proves like `data.prove(inputs)` and reports where the time and memory went.
+/// `timing_lines` returns the lines logged by `TimingTree::print` on the tree of the proof.
+pub fn prove_with_report<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    data: &CircuitData<F, C, D>,
+    inputs: PartialWitness<F>,
+    timing_lines: impl FnOnce(&TimingTree) -> Vec<String>,
+) -> Result<(ProofWithPublicInputs<F, C, D>, ProvingReport)> {
+    let sampler = Sampler::start();
+    let start = Instant::now();
+    let mut timing = TimingTree::new("prove", Level::Debug);
+    let proof = prove::<F, C, D>(&data.prover_only, &data.common, inputs, &mut timing);
+    let total = start.elapsed();
+    let samples = sampler.stop();
+    let proof = proof?;
+
+    // The root is `prove` itself.
+    let scopes = parse_timing_lines(&timing_lines(&timing))
+        .into_iter()
+        .next()
+        .map(|root| root.children)
+        .unwrap_or_default();
+
+    let report = ProvingReport {
+        total,
+        peak_memory: samples.iter().map(|&(_, rss)| rss).max(),
+        phases: phases(&scopes, &samples),
+        scopes,
+    };
+    Ok((proof, report))
+}
+
+/// Groups the top level scopes into phases. A scope is assumed to start when the previous one ends.
+fn phases(scopes: &[Scope], samples: &[(Duration, u64)]) -> Vec<PhaseReport> {
+    let mut windows: Vec<(Phase, Duration, Duration)> = Vec::new();
+    let mut offset = Duration::ZERO;
+    for scope in scopes.iter() {
+        windows.push((Phase::of_scope(&scope.name), offset, offset + scope.duration));
+        offset += scope.duration;
+    }
+
+    let rss_at = |t: Duration| samples.iter().rev().find(|&&(s, _)| s <= t).or(samples.first()).map(|&(_, rss)| rss);
+
+    Phase::ALL
+        .iter()
+        .filter_map(|&phase| {
+            let windows: Vec<(Duration, Duration)> =
+                windows.iter().filter(|w| w.0 == phase).map(|&(_, start, end)| (start, end)).collect();
+            if windows.is_empty() {
+                return None;
+            }
+
+            let in_phase = |t: Duration| windows.iter().any(|&(start, end)| start <= t && t <= end);
+            let deltas: Option<Vec<i64>> = windows
+                .iter()
+                .map(|&(start, end)| Some(rss_at(end)? as i64 - rss_at(start)? as i64))
+                .collect();
+            Some(PhaseReport {
+                phase,
+                duration: windows.iter().map(|&(start, end)| end - start).sum(),
+                peak_memory: samples.iter().filter(|&&(t, _)| in_phase(t)).map(|&(_, rss)| rss).max(),
+                memory_delta: deltas.map(|d| d.iter().sum()),
+            })
+        })
+        .collect()
+}
+
+/// Rebuilds the scopes from the `{seconds}s {"| " per depth}{name}` lines of `TimingTree::print`.
+fn parse_timing_lines(lines: &[String]) -> Vec<Scope> {
+    fn attach(stack: &mut Vec<(usize, Scope)>, roots: &mut Vec<Scope>, depth: usize) {
+        while stack.last().is_some_and(|(d, _)| *d >= depth) {
+            let (_, scope) = stack.pop().unwrap();
+            match stack.last_mut() {
+                Some((_, parent)) => parent.children.push(scope),
+                None => roots.push(scope),
+            }
+        }
+    }
+
+    let mut roots = Vec::new();
+    let mut stack: Vec<(usize, Scope)> = Vec::new();
+    for line in lines.iter() {
+        let Some((seconds, mut name)) = line.split_once("s ") else {
+            continue;
+        };
+        let Ok(seconds) = seconds.trim().parse::<f64>() else {
+            continue;
+        };
+        let mut depth = 0;
+        while let Some(rest) = name.strip_prefix("| ") {
+            name = rest;
+            depth += 1;
+        }
+
+        attach(&mut stack, &mut roots, depth);
+        let scope = Scope {
+            name: name.to_string(),
+            duration: Duration::from_secs_f64(seconds),
+            children: Vec::new(),
+        };
+        stack.push((depth, scope));
+    }
+    attach(&mut stack, &mut roots, 0);
+    roots
+}
+
+/// The resident set size of the process, in bytes.
+fn resident_memory() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|l| l.starts_with("VmRSS:"))?;
+    let kilobytes = line.split_whitespace().nth(1)?.parse::<u64>().ok()?;
+    Some(kilobytes * 1024)
+}
+
+/// Samples `resident_memory` on a thread until `stop`.
+struct Sampler {
+    stop: Arc<AtomicBool>,
+    handle: thread::JoinHandle<Vec<(Duration, u64)>>,
+}
+
+impl Sampler {
+    fn start() -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let start = Instant::now();
+        let handle = thread::spawn({
+            let stop = stop.clone();
+            move || {
+                let mut samples = Vec::new();
+                loop {
+                    if let Some(rss) = resident_memory() {
+                        samples.push((start.elapsed(), rss));
+                    }
+                    if stop.load(Ordering::SeqCst) {
+                        return samples;
+                    }
+                    thread::sleep(SAMPLE_INTERVAL);
+                }
+            }
+        });
+        Self { stop, handle }
+    }
+
+    fn stop(self) -> Vec<(Duration, u64)> {
+        self.stop.store(true, Ordering::SeqCst);
+        self.handle.join().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use plonky2::plonk::config::PoseidonGoldilocksConfig;
+
+    use crate::examples::Example;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    #[test]
+    fn parse_printed_tree() {
+        let lines: Vec<String> = [
+            "0.5000s prove",
+            "0.1000s | run 12 generators",
+            "0.2000s | compute wires commitment",
+            "0.0500s | | FFT + blinding",
+            "0.1000s | | transpose LDEs",
+            "0.1500s | compute quotient polys",
+            "0.0500s | compute opening proofs",
+        ]
+        .iter()
+        .map(|l| l.to_string())
+        .collect();
+
+        let roots = parse_timing_lines(&lines);
+        assert_eq!(roots.len(), 1);
+        let scopes = &roots[0].children;
+        assert_eq!(scopes.len(), 4);
+        assert_eq!(scopes[1].name, "compute wires commitment");
+        assert_eq!(scopes[1].children.len(), 2);
+        assert_eq!(scopes[1].children[1].duration, Duration::from_millis(100));
+
+        let samples = [(Duration::ZERO, 100), (Duration::from_millis(150), 300), (Duration::from_millis(400), 200)];
+        let phases = phases(scopes, &samples);
+        let names: Vec<Phase> = phases.iter().map(|p| p.phase).collect();
+        assert_eq!(
+            names,
+            [Phase::WitnessGeneration, Phase::WireCommitment, Phase::Quotient, Phase::Fri]
+        );
+        assert_eq!(phases[1].duration, Duration::from_millis(200));
+        assert_eq!(phases[1].peak_memory, Some(300));
+        assert_eq!(phases[1].memory_delta, Some(200));
+    }
+
+    #[test]
+    fn report() -> Result<()> {
+        let circuit = Example::MerkleMembership.build::<F, C, D>(4)?;
+        let pw = circuit.witness(&[("leaf".to_string(), 7), ("index".to_string(), 3)])?;
+        // Capturing the printed tree is up to the binary, these are lines as plonky2 logs them.
+        let lines = || {
+            ["0.0003s prove", "0.0001s | run 40 generators", "0.0001s | compute opening proofs"]
+                .iter()
+                .map(|l| l.to_string())
+                .collect()
+        };
+        let (proof, report) = prove_with_report(&circuit.data, pw, |_| lines())?;
+        circuit.data.verify(proof)?;
+
+        assert!(report.total > Duration::ZERO);
+        assert_eq!(report.scopes.len(), 2);
+        let phases: Vec<Phase> = report.phases.iter().map(|p| p.phase).collect();
+        assert_eq!(phases, [Phase::WitnessGeneration, Phase::Fri]);
+        assert_eq!(report.phase(Phase::Fri).unwrap().duration, Duration::from_micros(100));
+
+        let json: Value = serde_json::from_str(&report.to_json())?;
+        assert_eq!(json["phases"].as_array().unwrap().len(), report.phases.len());
+        assert!(json["total_seconds"].as_f64().unwrap() > 0.0);
+        Ok(())
+    }
+}
@@ -0,0 +1,90 @@
+use core::fmt;
+
+use plonky2::field::extension::Extendable;
+use plonky2::hash::hash_types::RichField;
+use plonky2::plonk::circuit_data::CircuitData;
+use plonky2::plonk::config::GenericConfig;
+
+use crate::analysis::mock_prover::row_gates;
+
+/// The size of a built circuit.
+#[derive(Debug, Clone)]
+pub struct CircuitStats {
+    pub degree_bits: usize,
+    pub num_wires: usize,
+    pub num_routed_wires: usize,
+    pub num_constants: usize,
+    pub num_public_inputs: usize,
+    pub quotient_degree_factor: usize,
+    pub num_partial_products: usize,
+    /// The id of every gate type and the number of rows using it, padding included.
+    pub gate_rows: Vec<(String, usize)>,
+    pub circuit_digest: String,
+}
+
+impl fmt::Display for CircuitStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "rows: {} (2^{})", 1usize << self.degree_bits, self.degree_bits)?;
+        writeln!(f, "wires: {} ({} routed)", self.num_wires, self.num_routed_wires)?;
+        writeln!(f, "constants: {}", self.num_constants)?;
+        writeln!(f, "public inputs: {}", self.num_public_inputs)?;
+        writeln!(f, "quotient degree factor: {}", self.quotient_degree_factor)?;
+        writeln!(f, "partial products: {}", self.num_partial_products)?;
+        writeln!(f, "circuit digest: {}", self.circuit_digest)?;
+        writeln!(f, "gates:")?;
+        for (gate, rows) in self.gate_rows.iter() {
+            writeln!(f, "    {rows:>8} {gate}")?;
+        }
+
+        Ok(())
+    }
+}
+
+pub fn circuit_stats<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    data: &CircuitData<F, C, D>,
+) -> CircuitStats {
+    let common = &data.common;
+
+    let mut rows = vec![0usize; common.gates.len()];
+    for gate_index in row_gates(data) {
+        rows[gate_index] += 1;
+    }
+
+    CircuitStats {
+        degree_bits: common.degree_bits(),
+        num_wires: common.config.num_wires,
+        num_routed_wires: common.config.num_routed_wires,
+        num_constants: common.num_constants,
+        num_public_inputs: common.num_public_inputs,
+        quotient_degree_factor: common.quotient_degree_factor,
+        num_partial_products: common.num_partial_products,
+        gate_rows: common.gates.iter().map(|g| g.0.id()).zip(rows).collect(),
+        circuit_digest: format!("{:?}", data.verifier_only.circuit_digest),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use plonky2::plonk::config::PoseidonGoldilocksConfig;
+
+    use crate::examples::Example;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    #[test]
+    fn gate_rows() -> anyhow::Result<()> {
+        let circuit = Example::MulAdd.build::<F, C, D>(1)?;
+        let stats = circuit_stats(&circuit.data);
+
+        assert_eq!(stats.gate_rows.iter().map(|(_, r)| r).sum::<usize>(), circuit.data.common.degree());
+        assert!(stats
+            .gate_rows
+            .iter()
+            .any(|(gate, rows)| gate.starts_with("SimpleMulAddTestGate") && *rows == 1));
+        Ok(())
+    }
+}
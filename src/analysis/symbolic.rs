@@ -0,0 +1,522 @@
+//! Prints the constraints of a gate as polynomials over named wires and constants.
+//!
+//! The private `Symbolic` is a `PackedField` of width one over Goldilocks, so the `eval_unfiltered_base_packed`
+//! of any gate implementing `PackedEvaluableBase` runs on it unchanged. A `Symbolic` is a handle into a
+//! thread-local expression arena, stored in the bits of a `GoldilocksField`: the strided views of plonky2
+//! reinterpret the scalars of a row as packed values, and read the constraints back the same way. The handles
+//! never leave this module, `symbolic_constraints` copies the constraints out of the arena as owned `Polynomial`s.
+//!
+//! Only the packed evaluation has a symbolic form. `eval_unfiltered` runs on the concrete `F::Extension` and
+//! `eval_unfiltered_circuit` emits arithmetic gates, neither can run on a custom type, so gates without a
+//! `PackedEvaluableBase` implementation cannot be printed. In this crate that is `SimpleMulAddExtensionGate`,
+//! which only evaluates over the extension algebra; in plonky2 it includes `ArithmeticExtensionGate` and
+//! `PoseidonGate`. The bound is checked at compile time:
+//!
+//! ```compile_fail
+//! use plonky2::plonk::circuit_data::CircuitConfig;
+//! use plonky2_cook::analysis::symbolic::print_constraints;
+//! use plonky2_cook::gates::simple_add_ext_gate::SimpleMulAddExtensionGate;
+//!
+//! let gate = SimpleMulAddExtensionGate::<2>::new_from_config(&CircuitConfig::standard_recursion_config());
+//! print_constraints::<_, 2>(&gate);
+//! ```
+//!
+//! For the same reason `check_symbolic_values` does not compare polynomials: it evaluates the printed ones on
+//! random points and compares the values with both other evaluations. A wrong constraint is then only caught
+//! with high probability, not proven equal.
+
+use core::cell::RefCell;
+use core::fmt;
+use core::iter::{Product, Sum};
+use core::ops::{Add, AddAssign, Div, Mul, MulAssign, Neg, Sub, SubAssign};
+
+use anyhow::{anyhow, Result};
+
+use plonky2::field::extension::Extendable;
+use plonky2::field::goldilocks_field::GoldilocksField;
+use plonky2::field::ops::Square;
+use plonky2::field::packed::PackedField;
+use plonky2::field::types::{Field, PrimeField64, Sample};
+use plonky2::gates::gate::Gate;
+use plonky2::gates::packed_util::PackedEvaluableBase;
+use plonky2::gates::util::StridedConstraintConsumer;
+use plonky2::hash::hash_types::HashOut;
+use plonky2::iop::generator::generate_partial_witness;
+use plonky2::iop::witness::{PartialWitness, Witness, WitnessWrite};
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+use plonky2::plonk::circuit_data::CircuitConfig;
+use plonky2::plonk::config::GenericConfig;
+use plonky2::plonk::vars::{EvaluationTargets, EvaluationVars, EvaluationVarsBasePacked};
+use plonky2::util::strided_view::PackedStridedView;
+
+type F = GoldilocksField;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Node {
+    Wire(usize),
+    Constant(usize),
+    Scalar(F),
+    Add(usize, usize),
+    Sub(usize, usize),
+    Mul(usize, usize),
+    Neg(usize),
+}
+
+thread_local! {
+    /// `ZEROS` and `ONES` are the handles 0 and 1.
+    static ARENA: RefCell<Vec<Node>> = RefCell::new(vec![Node::Scalar(F::ZERO), Node::Scalar(F::ONE)]);
+}
+
+/// A polynomial over the wires and constants of a row, as built by a gate evaluation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Polynomial {
+    Wire(usize),
+    Constant(usize),
+    Scalar(F),
+    Add(Box<Polynomial>, Box<Polynomial>),
+    Sub(Box<Polynomial>, Box<Polynomial>),
+    Mul(Box<Polynomial>, Box<Polynomial>),
+    Neg(Box<Polynomial>),
+}
+
+impl Polynomial {
+    fn from_arena(arena: &[Node], handle: usize) -> Self {
+        let tree = |h: usize| Box::new(Self::from_arena(arena, h));
+        match arena[handle] {
+            Node::Wire(i) => Polynomial::Wire(i),
+            Node::Constant(i) => Polynomial::Constant(i),
+            Node::Scalar(x) => Polynomial::Scalar(x),
+            Node::Add(a, b) => Polynomial::Add(tree(a), tree(b)),
+            Node::Sub(a, b) => Polynomial::Sub(tree(a), tree(b)),
+            Node::Mul(a, b) => Polynomial::Mul(tree(a), tree(b)),
+            Node::Neg(a) => Polynomial::Neg(tree(a)),
+        }
+    }
+
+    /// Evaluates the polynomial on `wires` and `constants`.
+    pub fn eval<E: Field + From<F>>(&self, wires: &[E], constants: &[E]) -> E {
+        match self {
+            Polynomial::Wire(i) => wires[*i],
+            Polynomial::Constant(i) => constants[*i],
+            Polynomial::Scalar(x) => (*x).into(),
+            Polynomial::Add(a, b) => a.eval(wires, constants) + b.eval(wires, constants),
+            Polynomial::Sub(a, b) => a.eval(wires, constants) - b.eval(wires, constants),
+            Polynomial::Mul(a, b) => a.eval(wires, constants) * b.eval(wires, constants),
+            Polynomial::Neg(a) => -a.eval(wires, constants),
+        }
+    }
+
+    /// Prints the polynomial, naming the wires and constants with `wire` and `constant`.
+    pub fn to_string_with(&self, wire: &dyn Fn(usize) -> String, constant: &dyn Fn(usize) -> String) -> String {
+        let mut out = String::new();
+        write_polynomial(self, 0, wire, constant, &mut out);
+        out
+    }
+}
+
+impl fmt::Display for Polynomial {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_string_with(&|i| format!("w{i}"), &|i| format!("c{i}")))
+    }
+}
+
+/// An expression being built by a gate evaluation, see the module documentation.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+#[repr(transparent)]
+struct Symbolic(F);
+
+impl Symbolic {
+    fn handle(self) -> usize {
+        self.0.to_noncanonical_u64() as usize
+    }
+
+    fn node(self) -> Node {
+        ARENA.with(|arena| arena.borrow()[self.handle()])
+    }
+
+    fn scalar_value(self) -> Option<F> {
+        match self.node() {
+            Node::Scalar(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Adds `node` to the arena, folding scalars and dropping additions of zero and multiplications by one.
+    fn push(node: Node) -> Self {
+        let scalar = |h: usize| Symbolic(F::from_noncanonical_u64(h as u64)).scalar_value();
+        let folded = match node {
+            Node::Add(a, b) | Node::Sub(a, b) | Node::Mul(a, b) => match (scalar(a), scalar(b), node) {
+                (Some(x), Some(y), Node::Add(..)) => Node::Scalar(x + y),
+                (Some(x), Some(y), Node::Sub(..)) => Node::Scalar(x - y),
+                (Some(x), Some(y), _) => Node::Scalar(x * y),
+                (Some(x), _, Node::Add(..)) if x == F::ZERO => return Self::from_handle(b),
+                (_, Some(y), Node::Add(..) | Node::Sub(..)) if y == F::ZERO => return Self::from_handle(a),
+                (Some(x), _, Node::Sub(..)) if x == F::ZERO => Node::Neg(b),
+                (Some(x), _, Node::Mul(..)) if x == F::ONE => return Self::from_handle(b),
+                (_, Some(y), Node::Mul(..)) if y == F::ONE => return Self::from_handle(a),
+                (Some(x), _, Node::Mul(..)) | (_, Some(x), Node::Mul(..)) if x == F::ZERO => return Self::ZEROS,
+                _ => node,
+            },
+            Node::Neg(a) => match scalar(a) {
+                Some(x) => Node::Scalar(-x),
+                None => node,
+            },
+            node => node,
+        };
+
+        ARENA.with(|arena| {
+            let mut arena = arena.borrow_mut();
+            arena.push(folded);
+            Self::from_handle(arena.len() - 1)
+        })
+    }
+
+    fn from_handle(handle: usize) -> Self {
+        Symbolic(F::from_noncanonical_u64(handle as u64))
+    }
+
+    fn wire(i: usize) -> Self {
+        Self::push(Node::Wire(i))
+    }
+
+    fn constant(i: usize) -> Self {
+        Self::push(Node::Constant(i))
+    }
+
+    fn to_polynomial(self) -> Polynomial {
+        ARENA.with(|arena| Polynomial::from_arena(&arena.borrow(), self.handle()))
+    }
+}
+
+/// Binding strength of a node, parentheses are added around children binding less than their parent needs.
+fn precedence(polynomial: &Polynomial) -> u8 {
+    match polynomial {
+        Polynomial::Add(..) | Polynomial::Sub(..) => 1,
+        Polynomial::Mul(..) => 2,
+        Polynomial::Neg(..) => 3,
+        Polynomial::Scalar(x) if x.to_canonical_u64() > F::ORDER / 2 => 1,
+        _ => 4,
+    }
+}
+
+fn write_polynomial(
+    polynomial: &Polynomial,
+    min_precedence: u8,
+    wire: &dyn Fn(usize) -> String,
+    constant: &dyn Fn(usize) -> String,
+    out: &mut String,
+) {
+    let parenthesize = precedence(polynomial) < min_precedence;
+    if parenthesize {
+        out.push('(');
+    }
+    let binary = |a: &Polynomial, op: &str, b: &Polynomial, left: u8, right: u8, out: &mut String| {
+        write_polynomial(a, left, wire, constant, out);
+        out.push_str(op);
+        write_polynomial(b, right, wire, constant, out);
+    };
+    match polynomial {
+        Polynomial::Wire(i) => out.push_str(&wire(*i)),
+        Polynomial::Constant(i) => out.push_str(&constant(*i)),
+        // Large scalars are printed as small negative numbers.
+        Polynomial::Scalar(x) if x.to_canonical_u64() > F::ORDER / 2 => {
+            out.push_str(&format!("-{}", (-*x).to_canonical_u64()))
+        }
+        Polynomial::Scalar(x) => out.push_str(&x.to_canonical_u64().to_string()),
+        Polynomial::Add(a, b) => binary(a, " + ", b, 1, 1, out),
+        Polynomial::Sub(a, b) => binary(a, " - ", b, 1, 2, out),
+        Polynomial::Mul(a, b) => binary(a, "*", b, 2, 3, out),
+        Polynomial::Neg(a) => {
+            out.push('-');
+            write_polynomial(a, 3, wire, constant, out);
+        }
+    }
+    if parenthesize {
+        out.push(')');
+    }
+}
+
+impl fmt::Debug for Symbolic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_polynomial())
+    }
+}
+
+impl From<F> for Symbolic {
+    fn from(x: F) -> Self {
+        Self::push(Node::Scalar(x))
+    }
+}
+
+macro_rules! impl_binary {
+    ($trait:ident, $method:ident, $assign_trait:ident, $assign_method:ident, $node:ident) => {
+        impl $trait for Symbolic {
+            type Output = Self;
+
+            fn $method(self, rhs: Self) -> Self {
+                Self::push(Node::$node(self.handle(), rhs.handle()))
+            }
+        }
+
+        impl $trait<F> for Symbolic {
+            type Output = Self;
+
+            fn $method(self, rhs: F) -> Self {
+                self.$method(Symbolic::from(rhs))
+            }
+        }
+
+        impl $trait<Symbolic> for F {
+            type Output = Symbolic;
+
+            fn $method(self, rhs: Symbolic) -> Symbolic {
+                Symbolic::from(self).$method(rhs)
+            }
+        }
+
+        impl $assign_trait for Symbolic {
+            fn $assign_method(&mut self, rhs: Self) {
+                *self = self.$method(rhs);
+            }
+        }
+
+        impl $assign_trait<F> for Symbolic {
+            fn $assign_method(&mut self, rhs: F) {
+                *self = self.$method(rhs);
+            }
+        }
+    };
+}
+
+impl_binary!(Add, add, AddAssign, add_assign, Add);
+impl_binary!(Sub, sub, SubAssign, sub_assign, Sub);
+impl_binary!(Mul, mul, MulAssign, mul_assign, Mul);
+
+impl Div<F> for Symbolic {
+    type Output = Self;
+
+    fn div(self, rhs: F) -> Self {
+        self * rhs.inverse()
+    }
+}
+
+impl Neg for Symbolic {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::push(Node::Neg(self.handle()))
+    }
+}
+
+impl Square for Symbolic {
+    fn square(&self) -> Self {
+        *self * *self
+    }
+}
+
+impl Sum for Symbolic {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::ZEROS, |acc, x| acc + x)
+    }
+}
+
+impl Product for Symbolic {
+    fn product<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::ONES, |acc, x| acc * x)
+    }
+}
+
+// SAFETY: `Symbolic` is a `repr(transparent)` `GoldilocksField`, like the width one packing of `F` itself.
+unsafe impl PackedField for Symbolic {
+    type Scalar = F;
+
+    const WIDTH: usize = 1;
+    const ZEROS: Self = Symbolic(F::ZERO);
+    const ONES: Self = Symbolic(F::ONE);
+
+    fn from_slice(slice: &[F]) -> &Self {
+        assert_eq!(slice.len(), 1);
+        unsafe { &*(slice.as_ptr() as *const Self) }
+    }
+
+    fn from_slice_mut(slice: &mut [F]) -> &mut Self {
+        assert_eq!(slice.len(), 1);
+        unsafe { &mut *(slice.as_mut_ptr() as *mut Self) }
+    }
+
+    fn as_slice(&self) -> &[F] {
+        core::slice::from_ref(&self.0)
+    }
+
+    fn as_slice_mut(&mut self) -> &mut [F] {
+        core::slice::from_mut(&mut self.0)
+    }
+
+    fn interleave(&self, other: Self, block_len: usize) -> (Self, Self) {
+        assert_eq!(block_len, 1);
+        (*self, other)
+    }
+}
+
+/// The constraints of `gate`, as evaluated by `eval_unfiltered_base_packed`, as polynomials in its wires and constants.
+pub fn symbolic_constraints<G, const D: usize>(gate: &G) -> Vec<Polynomial>
+where
+    F: Extendable<D>,
+    G: Gate<F, D> + PackedEvaluableBase<F, D>,
+{
+    ARENA.with(|arena| arena.borrow_mut().truncate(2));
+
+    let wires: Vec<F> = (0..gate.num_wires()).map(|i| Symbolic::wire(i).0).collect();
+    let constants: Vec<F> = (0..gate.num_constants()).map(|i| Symbolic::constant(i).0).collect();
+    let public_inputs_hash = HashOut::ZERO;
+    let vars = EvaluationVarsBasePacked::<Symbolic> {
+        local_constants: PackedStridedView::new(&constants, 1, 0),
+        local_wires: PackedStridedView::new(&wires, 1, 0),
+        public_inputs_hash: &public_inputs_hash,
+    };
+
+    let mut constraints = vec![F::ZERO; gate.num_constraints()];
+    gate.eval_unfiltered_base_packed(vars, StridedConstraintConsumer::new(&mut constraints, 1, 0));
+    constraints.into_iter().map(|c| Symbolic(c).to_polynomial()).collect()
+}
+
+/// Prints every constraint of `gate`, the wires are `w{i}` and the constants `c{i}`.
+pub fn print_constraints<G, const D: usize>(gate: &G) -> Vec<String>
+where
+    F: Extendable<D>,
+    G: Gate<F, D> + PackedEvaluableBase<F, D>,
+{
+    symbolic_constraints(gate).iter().map(|c| c.to_string()).collect()
+}
+
+/// Checks that the printed constraints of `gate` take the same values as its native and recursive evaluations
+/// on `num_trials` random points of the extension field. The polynomials themselves are not compared,
+/// see the module documentation.
+pub fn check_symbolic_values<G, C, const D: usize>(gate: &G, num_trials: usize) -> Result<()>
+where
+    F: Extendable<D>,
+    G: Gate<F, D> + PackedEvaluableBase<F, D>,
+    C: GenericConfig<D, F = F>,
+{
+    let constraints = symbolic_constraints(gate);
+
+    // The recursive evaluation is built once, and run by witness generation on every point.
+    let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+    let wire_targets = builder.add_virtual_extension_targets(gate.num_wires());
+    let constant_targets = builder.add_virtual_extension_targets(gate.num_constants());
+    let hash_target = builder.add_virtual_hash();
+    let outputs = gate.eval_unfiltered_circuit(
+        &mut builder,
+        EvaluationTargets {
+            local_constants: &constant_targets,
+            local_wires: &wire_targets,
+            public_inputs_hash: &hash_target,
+        },
+    );
+    let data = builder.build::<C>();
+
+    for _ in 0..num_trials {
+        let wires = F::Extension::rand_vec(gate.num_wires());
+        let constants = F::Extension::rand_vec(gate.num_constants());
+        let public_inputs_hash = HashOut::ZERO;
+
+        let native = gate.eval_unfiltered(EvaluationVars {
+            local_constants: &constants,
+            local_wires: &wires,
+            public_inputs_hash: &public_inputs_hash,
+        });
+
+        let mut pw = PartialWitness::new();
+        for (&t, &v) in wire_targets.iter().zip(wires.iter()).chain(constant_targets.iter().zip(constants.iter())) {
+            pw.set_extension_target(t, v);
+        }
+        pw.set_hash_target(hash_target, public_inputs_hash);
+        let witness = generate_partial_witness(pw, &data.prover_only, &data.common);
+
+        for (i, constraint) in constraints.iter().enumerate() {
+            let symbolic = constraint.eval(&wires, &constants);
+            if symbolic != native[i] {
+                return Err(anyhow!("{}: constraint {i} `{constraint}` differs from `eval_unfiltered`", gate.id()));
+            }
+            if symbolic != witness.get_extension_target(outputs[i]) {
+                return Err(anyhow!("{}: constraint {i} `{constraint}` differs from `eval_unfiltered_circuit`", gate.id()));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use plonky2::plonk::config::PoseidonGoldilocksConfig;
+
+    use crate::gates::exp_var_gate::SimpleExpVarGate;
+    use crate::gates::expression_gate::{Expr, Expression, ExpressionGate};
+    use crate::gates::g_w_v_v_constant::SimpleExpConstantGate;
+    use crate::gates::g_w_v_v_low_degree::SimpleExpLowDegreeGate;
+    use crate::gates::gate_with_veriable_vars::SimpleExpTestGate;
+    use crate::gates::simple_add_gate::SimpleMulAddTestGate;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+
+    #[test]
+    fn mul_add() {
+        let gate = SimpleMulAddTestGate { num_ops: 2 };
+        let constraints = print_constraints::<_, D>(&gate);
+        assert_eq!(constraints, vec!["w5 - (w0*w1 + w2 + w3 + w4)", "w11 - (w6*w7 + w8 + w9 + w10)"]);
+    }
+
+    #[test]
+    fn owned_constraints() {
+        let first = symbolic_constraints::<_, D>(&SimpleMulAddTestGate { num_ops: 1 });
+        // A second evaluation reuses the arena, the constraints of the first one are not affected.
+        let config = CircuitConfig::standard_recursion_config();
+        symbolic_constraints::<_, D>(&SimpleExpVarGate::new(2, &config));
+        assert_eq!(first[0].to_string(), "w5 - (w0*w1 + w2 + w3 + w4)");
+        assert_eq!(first[0].eval(&[F::TWO, F::TWO, F::ONE, F::ONE, F::ONE, F::ONE], &[]), -F::from_canonical_u32(6));
+    }
+
+    #[test]
+    fn expression() {
+        let config = CircuitConfig::standard_recursion_config();
+        let expression = Expression::new(&["x", "y"], "out", &["c"], Expr::constant("c") * Expr::wire("x") - Expr::wire("y"));
+        let gate = ExpressionGate::new_from_config(expression, &config);
+
+        let constraints = print_constraints::<_, D>(&gate);
+        assert_eq!(constraints[0], "w2 - (c0*w0 - w1)");
+    }
+
+    // `SimpleMulAddExtensionGate` has no packed evaluation, the module documentation checks that it is rejected.
+    #[test]
+    fn cook_gates() -> Result<()> {
+        let config = CircuitConfig::standard_recursion_config();
+        check_symbolic_values::<_, C, D>(&SimpleMulAddTestGate::new_from_config(&config), 2)?;
+        check_symbolic_values::<_, C, D>(&SimpleExpTestGate::new(8, &config), 2)?;
+        check_symbolic_values::<_, C, D>(&SimpleExpConstantGate::new(8, &config), 2)?;
+        check_symbolic_values::<_, C, D>(&SimpleExpLowDegreeGate::new(8, &config), 2)?;
+        check_symbolic_values::<_, C, D>(&SimpleExpVarGate::new(8, &config), 2)?;
+        check_symbolic_values::<_, C, D>(
+            &ExpressionGate::new_from_config(
+                Expression::new(&["x", "y"], "out", &["c"], Expr::constant("c") * Expr::wire("x") + Expr::wire("y")),
+                &config,
+            ),
+            2,
+        )
+    }
+
+    #[test]
+    fn exp_var() {
+        let config = CircuitConfig::standard_recursion_config();
+        let gate = SimpleExpVarGate::new(2, &config);
+        let constraints = print_constraints::<_, D>(&gate);
+
+        // The most significant bit `w2` comes first, and the first accumulator starts from one.
+        assert_eq!(constraints[0], "w2*(w2 - 1)");
+        assert_eq!(constraints[1], "w4 - (w2*w0 + 1 - w2)");
+        assert_eq!(constraints.len(), 5);
+    }
+}
@@ -0,0 +1,359 @@
+//! The full wire matrix of a generated witness, as CSV or as a compact binary file.
+//!
+//! ```text
+//! magic            8 bytes   b"COOKTRC\0"
+//! version          u32 LE
+//! num_wires        u32 LE
+//! num_constants    u32 LE, gate constants per row
+//! gate ids         u32 LE count, then u32 LE length + UTF-8 each
+//! num_rows         u64 LE
+//! rows             u64 LE row, u32 LE gate id index, constants and wires as u64 LE,
+//!                  then a bitmask of the copied wires, `ceil(num_wires / 8)` bytes
+//! ```
+
+use core::ops::Range;
+
+use anyhow::{anyhow, Result};
+
+use plonky2::field::extension::Extendable;
+use plonky2::field::types::{Field, PrimeField64};
+use plonky2::hash::hash_types::RichField;
+use plonky2::iop::witness::PartialWitness;
+use plonky2::plonk::circuit_data::CircuitData;
+use plonky2::plonk::config::GenericConfig;
+
+use crate::analysis::mock_prover::{gate_constant_values, MockProver};
+
+pub const TRACE_MAGIC: [u8; 8] = *b"COOKTRC\0";
+pub const TRACE_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Default)]
+pub struct TraceOptions {
+    /// Only these rows, all of them if `None`.
+    pub rows: Option<Range<usize>>,
+    /// Mark the wires which are in a copy constraint with another wire.
+    pub highlight_copies: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceRow<F: Field> {
+    pub row: usize,
+    /// Index into `Trace::gates`.
+    pub gate: usize,
+    pub constants: Vec<F>,
+    pub wires: Vec<F>,
+    /// `copied[column]`, all `false` without `TraceOptions::highlight_copies`.
+    pub copied: Vec<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trace<F: Field> {
+    pub num_wires: usize,
+    pub num_constants: usize,
+    /// The `id()` of every gate of the circuit.
+    pub gates: Vec<String>,
+    pub rows: Vec<TraceRow<F>>,
+}
+
+/// Runs witness generation of `data` on `inputs` and collects the rows selected by `options`.
+pub fn generate_trace<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    data: &CircuitData<F, C, D>,
+    inputs: PartialWitness<F>,
+    options: &TraceOptions,
+) -> Result<Trace<F>> {
+    trace_of(&MockProver::new(data, inputs)?, options)
+}
+
+/// The trace of the wires of `prover`, which may have been changed with `MockProver::set_wire`.
+pub fn trace_of<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    prover: &MockProver<F, C, D>,
+    options: &TraceOptions,
+) -> Result<Trace<F>> {
+    let common = &prover.data.common;
+    let (num_wires, degree) = (common.config.num_wires, common.degree());
+    let rows = options.rows.clone().unwrap_or(0..degree);
+    if rows.start > rows.end || rows.end > degree {
+        return Err(anyhow!("rows {rows:?} are not within the {degree} rows of the circuit"));
+    }
+
+    // Only routed wires are in the permutation, a partition with one wire has no copy constraint.
+    let representative_map = &prover.data.prover_only.representative_map;
+    let mut partition_sizes = vec![0usize; representative_map.len()];
+    if options.highlight_copies {
+        for row in 0..degree {
+            for column in 0..common.config.num_routed_wires {
+                partition_sizes[representative_map[row * num_wires + column]] += 1;
+            }
+        }
+    }
+
+    let row_gates = prover.row_gates();
+    let constants = gate_constant_values(prover.data);
+    let rows = rows
+        .map(|row| TraceRow {
+            row,
+            gate: row_gates[row],
+            constants: constants.iter().map(|c| c.values[row]).collect(),
+            wires: prover.wires[row].clone(),
+            copied: (0..num_wires)
+                .map(|column| partition_sizes[representative_map[row * num_wires + column]] > 1)
+                .collect(),
+        })
+        .collect();
+
+    Ok(Trace {
+        num_wires,
+        num_constants: constants.len(),
+        gates: common.gates.iter().map(|g| g.0.id()).collect(),
+        rows,
+    })
+}
+
+/// Reads a binary trace front to back.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn remaining(&self) -> usize {
+        self.bytes.len() - self.pos
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.remaining() < len {
+            return Err(anyhow!("the trace is truncated"));
+        }
+        let bytes = &self.bytes[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn read_u32(&mut self) -> Result<usize> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()) as usize)
+    }
+
+    fn read_u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+}
+
+impl<F: RichField> Trace<F> {
+    /// One line per row: `row,gate,c0,...,w0,...`. Copied wires end with a `*`.
+    pub fn to_csv(&self) -> String {
+        let mut header = vec!["row".to_string(), "gate".to_string()];
+        header.extend((0..self.num_constants).map(|i| format!("c{i}")));
+        header.extend((0..self.num_wires).map(|i| format!("w{i}")));
+
+        let mut csv = header.join(",");
+        csv.push('\n');
+        for r in self.rows.iter() {
+            let mut cells = vec![r.row.to_string(), format!("\"{}\"", self.gates[r.gate].replace('"', "\"\""))];
+            cells.extend(r.constants.iter().map(|c| c.to_canonical_u64().to_string()));
+            cells.extend(r.wires.iter().zip(r.copied.iter()).map(|(w, &copied)| {
+                let value = w.to_canonical_u64().to_string();
+                if copied {
+                    value + "*"
+                } else {
+                    value
+                }
+            }));
+            csv.push_str(&cells.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&TRACE_MAGIC);
+        bytes.extend_from_slice(&TRACE_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&(self.num_wires as u32).to_le_bytes());
+        bytes.extend_from_slice(&(self.num_constants as u32).to_le_bytes());
+        bytes.extend_from_slice(&(self.gates.len() as u32).to_le_bytes());
+        for id in self.gates.iter() {
+            bytes.extend_from_slice(&(id.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(id.as_bytes());
+        }
+
+        bytes.extend_from_slice(&(self.rows.len() as u64).to_le_bytes());
+        for r in self.rows.iter() {
+            bytes.extend_from_slice(&(r.row as u64).to_le_bytes());
+            bytes.extend_from_slice(&(r.gate as u32).to_le_bytes());
+            for value in r.constants.iter().chain(r.wires.iter()) {
+                bytes.extend_from_slice(&value.to_canonical_u64().to_le_bytes());
+            }
+            let mut mask = vec![0u8; self.num_wires.div_ceil(8)];
+            for (column, _) in r.copied.iter().enumerate().filter(|(_, &c)| c) {
+                mask[column / 8] |= 1 << (column % 8);
+            }
+            bytes.extend_from_slice(&mask);
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut reader = Reader { bytes, pos: 0 };
+
+        if reader.take(TRACE_MAGIC.len())? != TRACE_MAGIC {
+            return Err(anyhow!("not a trace file"));
+        }
+        let version = reader.read_u32()? as u32;
+        if version != TRACE_VERSION {
+            return Err(anyhow!("unsupported trace version {version}, expected {TRACE_VERSION}"));
+        }
+        let num_wires = reader.read_u32()?;
+        let num_constants = reader.read_u32()?;
+        let num_gates = reader.read_u32()?;
+
+        // The counts come from the file, they are checked against its length before anything is allocated.
+        if num_gates * 4 > reader.remaining() {
+            return Err(anyhow!("{num_gates} gates do not fit in the trace"));
+        }
+        let mut gates = Vec::with_capacity(num_gates);
+        for _ in 0..num_gates {
+            let len = reader.read_u32()?;
+            gates.push(String::from_utf8(reader.take(len)?.to_vec())?);
+        }
+
+        let num_rows = reader.read_u64()?;
+        let mut rows = Vec::new();
+        for _ in 0..num_rows {
+            let row = reader.read_u64()? as usize;
+            let gate = reader.read_u32()?;
+            if gate >= gates.len() {
+                return Err(anyhow!("row {row} uses gate {gate}, there are {}", gates.len()));
+            }
+
+            if (num_constants + num_wires) * 8 > reader.remaining() {
+                return Err(anyhow!("the trace is truncated"));
+            }
+            let mut values = Vec::with_capacity(num_constants + num_wires);
+            for _ in 0..num_constants + num_wires {
+                let value = reader.read_u64()?;
+                if value >= F::ORDER {
+                    return Err(anyhow!("row {row}: {value} is not a canonical field element"));
+                }
+                values.push(F::from_canonical_u64(value));
+            }
+            let mask = reader.take(num_wires.div_ceil(8))?;
+
+            rows.push(TraceRow {
+                row,
+                gate,
+                wires: values.split_off(num_constants),
+                constants: values,
+                copied: (0..num_wires).map(|c| mask[c / 8] & (1 << (c % 8)) != 0).collect(),
+            });
+        }
+
+        if reader.remaining() != 0 {
+            return Err(anyhow!("{} unexpected bytes after the trace", reader.remaining()));
+        }
+
+        Ok(Trace {
+            num_wires,
+            num_constants,
+            gates,
+            rows,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use plonky2::iop::target::Target;
+    use plonky2::iop::witness::WitnessWrite;
+    use plonky2::plonk::circuit_builder::CircuitBuilder;
+    use plonky2::plonk::circuit_data::CircuitConfig;
+    use plonky2::plonk::config::PoseidonGoldilocksConfig;
+
+    use crate::gates::g_w_v_v_constant::SimpleExpConstantGate;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    /// `3^5` with one `SimpleExpConstantGate`, then `+ x`.
+    fn exp_constant_circuit() -> (CircuitData<F, C, D>, Target, usize, SimpleExpConstantGate) {
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config.clone());
+
+        let gate = SimpleExpConstantGate::new(5, &config);
+        let row = builder.add_gate(gate.clone(), vec![F::from_canonical_u64(3)]);
+        let x = builder.add_virtual_target();
+        let sum = builder.add(Target::wire(row, gate.wire_output()), x);
+        builder.register_public_input(sum);
+
+        (builder.build::<C>(), x, row, gate)
+    }
+
+    #[test]
+    fn exp_constant_row() -> Result<()> {
+        let (data, x, row, gate) = exp_constant_circuit();
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::ONE);
+
+        let options = TraceOptions {
+            rows: Some(row..row + 1),
+            highlight_copies: true,
+        };
+        let trace = generate_trace(&data, pw, &options)?;
+
+        let r = &trace.rows[0];
+        assert_eq!(r.row, row);
+        assert!(trace.gates[r.gate].starts_with("SimpleExpConstantGate"));
+        assert_eq!(r.constants[0], F::from_canonical_u64(3));
+        assert_eq!(r.wires[gate.wire_output()], F::from_canonical_u64(243));
+        // Only the output is copied to the `ArithmeticGate` of the addition.
+        assert_eq!(r.copied.iter().filter(|&&c| c).count(), 1);
+        assert!(r.copied[gate.wire_output()]);
+        Ok(())
+    }
+
+    #[test]
+    fn csv_and_binary() -> Result<()> {
+        let (data, x, _, _) = exp_constant_circuit();
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::ONE);
+
+        let options = TraceOptions {
+            rows: None,
+            highlight_copies: true,
+        };
+        let trace = generate_trace(&data, pw, &options)?;
+        assert_eq!(trace.rows.len(), data.common.degree());
+
+        let csv = trace.to_csv();
+        assert_eq!(csv.lines().count(), trace.rows.len() + 1);
+        assert!(csv.contains("243*"));
+
+        let bytes = trace.to_bytes();
+        assert_eq!(Trace::<F>::from_bytes(&bytes)?, trace);
+        assert!(Trace::<F>::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+
+        // Huge counts in the header are rejected before they are allocated: `num_wires`, then the gate count.
+        for offset in [12, 20] {
+            let mut corrupt = bytes.clone();
+            corrupt[offset..offset + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+            assert!(Trace::<F>::from_bytes(&corrupt).is_err());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn rows_out_of_range() {
+        let (data, x, _, _) = exp_constant_circuit();
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::ONE);
+
+        let degree = data.common.degree();
+        let options = TraceOptions {
+            rows: Some(0..degree + 1),
+            highlight_copies: false,
+        };
+        assert!(generate_trace(&data, pw, &options).is_err());
+    }
+}
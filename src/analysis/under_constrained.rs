@@ -0,0 +1,336 @@
+use core::fmt;
+
+use anyhow::{anyhow, Result};
+
+use plonky2::field::extension::Extendable;
+use plonky2::field::types::{Field, Sample};
+use plonky2::gates::gate::Gate;
+use plonky2::hash::hash_types::{HashOut, RichField};
+use plonky2::iop::generator::{GeneratedValues, WitnessGeneratorRef};
+use plonky2::iop::target::Target;
+use plonky2::iop::witness::{PartitionWitness, Witness, WitnessWrite};
+use plonky2::plonk::vars::EvaluationVars;
+
+#[derive(Debug, Clone)]
+pub struct UnderConstrainedReport {
+    pub gate: String,
+    /// Wires which can be changed on every tried assignment without violating any constraint.
+    pub unconstrained_wires: Vec<usize>,
+    /// Wires which are neither read nor written by the generators of the gate, they were filled with random values.
+    pub free_wires: Vec<usize>,
+}
+
+impl UnderConstrainedReport {
+    pub fn is_ok(&self) -> bool {
+        self.unconstrained_wires.is_empty()
+    }
+}
+
+impl fmt::Display for UnderConstrainedReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_ok() {
+            return writeln!(f, "{}: every wire is constrained", self.gate);
+        }
+
+        for &w in self.unconstrained_wires.iter() {
+            let note = if self.free_wires.contains(&w) { ", it is not used by the generators either" } else { "" };
+            writeln!(f, "{}: wire {} is potentially under-constrained{}", self.gate, w, note)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Samples any field element for every input column, the default of `generate_gate_wires`.
+pub fn random_input<F: Sample>(_column: usize) -> F {
+    F::rand()
+}
+
+/// Builds a random satisfying assignment of one row of `gate` with its own generators.
+/// The generator inputs, i.e. the targets no generator writes, are drawn from `sample_input` with their column,
+/// so gates whose inputs are themselves constrained, e.g. bits, can be given valid ones. The unused wires are random.
+/// Returns the wires and the indices of the unused wires.
+pub fn generate_gate_wires<F: RichField + Extendable<D>, G: Gate<F, D>, const D: usize>(
+    gate: &G,
+    constants: &[F],
+    sample_input: &dyn Fn(usize) -> F,
+) -> Result<(Vec<F>, Vec<usize>)> {
+    generate_wires(&gate.generators(0, constants), gate.num_wires(), sample_input)
+}
+
+/// The targets `generator` writes, found by running it once on a row of random wires.
+fn generator_outputs<F: RichField + Extendable<D>, const D: usize>(
+    generator: &WitnessGeneratorRef<F, D>,
+    num_wires: usize,
+) -> Vec<Target> {
+    let representative_map: Vec<usize> = (0..num_wires).collect();
+    let mut witness = PartitionWitness::new(num_wires, 1, &representative_map);
+    for column in 0..num_wires {
+        witness.set_target(Target::wire(0, column), F::rand());
+    }
+
+    let mut out_buffer = GeneratedValues::empty();
+    generator.0.run(&witness, &mut out_buffer);
+    out_buffer.target_values.into_iter().map(|(t, _)| t).collect()
+}
+
+/// `generate_gate_wires` for the `generators` of a row of `num_wires` wires.
+fn generate_wires<F: RichField + Extendable<D>, const D: usize>(
+    generators: &[WitnessGeneratorRef<F, D>],
+    num_wires: usize,
+    sample_input: &dyn Fn(usize) -> F,
+) -> Result<(Vec<F>, Vec<usize>)> {
+    let representative_map: Vec<usize> = (0..num_wires).collect();
+    let mut witness = PartitionWitness::new(num_wires, 1, &representative_map);
+
+    let outputs: Vec<Vec<Target>> = generators.iter().map(|g| generator_outputs(g, num_wires)).collect();
+    let mut pending: Vec<usize> = (0..generators.len()).collect();
+    while !pending.is_empty() {
+        let mut progress = false;
+        let mut still_pending = Vec::new();
+        for &i in pending.iter() {
+            let watch_list = generators[i].0.watch_list();
+            if watch_list.iter().any(|&t| witness.try_get_target(t).is_none()) {
+                still_pending.push(i);
+                continue;
+            }
+
+            let mut out_buffer = GeneratedValues::empty();
+            if !generators[i].0.run(&witness, &mut out_buffer) {
+                still_pending.push(i);
+                continue;
+            }
+            for (t, v) in out_buffer.target_values {
+                witness.set_target(t, v);
+            }
+            progress = true;
+        }
+
+        // Nothing can run anymore, the missing dependencies of the first waiting generator are inputs, except
+        // the ones a waiting generator writes later: setting them now would make it set them twice.
+        if !progress && !still_pending.is_empty() {
+            let is_output = |t: &Target| still_pending.iter().any(|&i| outputs[i].contains(t));
+            let inputs: Vec<Target> = still_pending
+                .iter()
+                .map(|&i| {
+                    generators[i]
+                        .0
+                        .watch_list()
+                        .into_iter()
+                        .filter(|t| witness.try_get_target(*t).is_none() && !is_output(t))
+                        .collect::<Vec<Target>>()
+                })
+                .find(|inputs| !inputs.is_empty())
+                .unwrap_or_default();
+            if inputs.is_empty() {
+                return Err(anyhow!(
+                    "generator {} does not run with its dependencies set",
+                    generators[still_pending[0]].0.id()
+                ));
+            }
+            for t in inputs {
+                let value = match t {
+                    Target::Wire(w) => sample_input(w.column),
+                    Target::VirtualTarget { .. } => F::rand(),
+                };
+                witness.set_target(t, value);
+            }
+        }
+        pending = still_pending;
+    }
+
+    let mut free_wires = Vec::new();
+    let wires = (0..num_wires)
+        .map(|column| {
+            witness.try_get_target(Target::wire(0, column)).unwrap_or_else(|| {
+                free_wires.push(column);
+                F::rand()
+            })
+        })
+        .collect();
+
+    Ok((wires, free_wires))
+}
+
+fn eval<F: RichField + Extendable<D>, G: Gate<F, D>, const D: usize>(
+    gate: &G,
+    wires: &[F],
+    constants: &[F::Extension],
+    public_inputs_hash: &HashOut<F>,
+) -> Vec<F::Extension> {
+    let local_wires: Vec<F::Extension> = wires.iter().map(|&w| w.into()).collect();
+    gate.eval_unfiltered(EvaluationVars {
+        local_constants: constants,
+        local_wires: &local_wires,
+        public_inputs_hash,
+    })
+}
+
+/// Changes every wire of `gate`, one at a time, on `num_trials` random satisfying assignments,
+/// and reports the wires whose change never violates a constraint of `eval_unfiltered`.
+/// `constants` are the gate constants of the row, as given to `Gate::generators`, and `sample_input` draws the
+/// generator inputs, see `generate_gate_wires`.
+pub fn find_under_constrained_wires<F: RichField + Extendable<D>, G: Gate<F, D>, const D: usize>(
+    gate: &G,
+    constants: &[F],
+    sample_input: &dyn Fn(usize) -> F,
+    num_trials: usize,
+) -> Result<UnderConstrainedReport> {
+    let num_wires = gate.num_wires();
+    let local_constants: Vec<F::Extension> = constants.iter().map(|&c| c.into()).collect();
+    let public_inputs_hash = HashOut::rand();
+
+    let mut constrained = vec![false; num_wires];
+    let mut free_wires = Vec::new();
+    for _ in 0..num_trials {
+        let (mut wires, free) = generate_gate_wires(gate, constants, sample_input)?;
+        free_wires = free;
+
+        let constraints = eval(gate, &wires, &local_constants, &public_inputs_hash);
+        if let Some(i) = constraints.iter().position(|c| *c != F::Extension::ZERO) {
+            return Err(anyhow!(
+                "the generators of {} produce an assignment violating constraint {}",
+                gate.id(),
+                i
+            ));
+        }
+
+        for column in 0..num_wires {
+            if constrained[column] {
+                continue;
+            }
+
+            let original = wires[column];
+            wires[column] = original + F::rand() + F::ONE;
+            constrained[column] = eval(gate, &wires, &local_constants, &public_inputs_hash)
+                .iter()
+                .any(|c| *c != F::Extension::ZERO);
+            wires[column] = original;
+        }
+    }
+
+    Ok(UnderConstrainedReport {
+        gate: gate.id(),
+        unconstrained_wires: (0..num_wires).filter(|&w| !constrained[w]).collect(),
+        free_wires,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use plonky2::field::types::PrimeField64;
+    use plonky2::iop::generator::SimpleGenerator;
+    use plonky2::plonk::circuit_data::{CircuitConfig, CommonCircuitData};
+    use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use plonky2::util::serialization::{Buffer, IoResult, Read, Write};
+
+    use crate::gates::exp_var_gate::SimpleExpVarGate;
+    use crate::gates::expression_gate::{Expr, Expression, ExpressionGate};
+    use crate::gates::g_w_v_v_constant::SimpleExpConstantGate;
+    use crate::gates::g_w_v_v_low_degree::SimpleExpLowDegreeGate;
+    use crate::gates::gate_with_veriable_vars::SimpleExpTestGate;
+    use crate::gates::simple_add_ext_gate::SimpleMulAddExtensionGate;
+    use crate::gates::simple_add_gate::SimpleMulAddTestGate;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    fn assert_constrained<G: Gate<F, D>>(gate: G, constants: &[F]) -> Result<()> {
+        assert_constrained_with(gate, constants, &random_input::<F>)
+    }
+
+    fn assert_constrained_with<G: Gate<F, D>>(gate: G, constants: &[F], sample_input: &dyn Fn(usize) -> F) -> Result<()> {
+        let report = find_under_constrained_wires(&gate, constants, sample_input, 3)?;
+        assert!(report.is_ok(), "{report}");
+        Ok(())
+    }
+
+    #[test]
+    fn cook_gates() -> Result<()> {
+        let config = CircuitConfig::standard_recursion_config();
+        assert_constrained(SimpleMulAddTestGate::new_from_config(&config), &[])?;
+        assert_constrained(SimpleExpTestGate::new(8, &config), &[])?;
+        assert_constrained(SimpleExpConstantGate::new(8, &config), &[F::rand()])?;
+        assert_constrained(SimpleExpLowDegreeGate::new(8, &config), &[])?;
+        // The exponent bits are inputs checked by `bit * (bit - 1)`, they must be sampled as bits.
+        let exp_var = SimpleExpVarGate::new(8, &config);
+        let bits: Vec<usize> = (0..8).map(|i| exp_var.wire_exponent_bit(i)).collect();
+        let sample_bit = |column: usize| {
+            if bits.contains(&column) {
+                F::from_bool(F::rand().to_canonical_u64() & 1 == 1)
+            } else {
+                F::rand()
+            }
+        };
+        assert_constrained_with(exp_var, &[], &sample_bit)?;
+        assert_constrained(SimpleMulAddExtensionGate::<D>::new_from_config(&config), &[])
+    }
+
+    #[test]
+    fn unused_input() -> Result<()> {
+        let config = CircuitConfig::standard_recursion_config();
+        // `y` is routed into the gate but never appears in the constraint.
+        let expression = Expression::new(&["x", "y"], "out", &[], Expr::wire("x") * Expr::wire("x"));
+        let gate = ExpressionGate::new_from_config(expression, &config);
+
+        let report = find_under_constrained_wires::<F, _, D>(&gate, &[], &random_input::<F>, 3)?;
+        let expected: Vec<usize> = (0..gate.num_ops).map(|i| gate.wire_ith_input(i, 1)).collect();
+        assert_eq!(report.unconstrained_wires, expected);
+        Ok(())
+    }
+
+    /// `output = a * b` on row 0.
+    #[derive(Debug)]
+    struct MulGenerator {
+        a: usize,
+        b: usize,
+        output: usize,
+    }
+
+    impl SimpleGenerator<F, D> for MulGenerator {
+        fn id(&self) -> String {
+            format!("{self:?}")
+        }
+
+        fn dependencies(&self) -> Vec<Target> {
+            vec![Target::wire(0, self.a), Target::wire(0, self.b)]
+        }
+
+        fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+            let value = witness.get_target(Target::wire(0, self.a)) * witness.get_target(Target::wire(0, self.b));
+            out_buffer.set_target(Target::wire(0, self.output), value);
+        }
+
+        fn serialize(&self, dst: &mut Vec<u8>, _common_data: &CommonCircuitData<F, D>) -> IoResult<()> {
+            dst.write_usize(self.a)?;
+            dst.write_usize(self.b)?;
+            dst.write_usize(self.output)
+        }
+
+        fn deserialize(src: &mut Buffer, _common_data: &CommonCircuitData<F, D>) -> IoResult<Self> {
+            Ok(Self {
+                a: src.read_usize()?,
+                b: src.read_usize()?,
+                output: src.read_usize()?,
+            })
+        }
+    }
+
+    #[test]
+    fn input_written_later() -> Result<()> {
+        // The first generator waits on wire 1, which the second one writes from wire 3: only wires 0 and 3 are inputs.
+        let generators = vec![
+            WitnessGeneratorRef::new(MulGenerator { a: 0, b: 1, output: 2 }.adapter()),
+            WitnessGeneratorRef::new(MulGenerator { a: 3, b: 3, output: 1 }.adapter()),
+        ];
+
+        let (wires, free_wires) = generate_wires(&generators, 4, &random_input::<F>)?;
+        assert_eq!(wires[1], wires[3] * wires[3]);
+        assert_eq!(wires[2], wires[0] * wires[1]);
+        assert!(free_wires.is_empty());
+        Ok(())
+    }
+}
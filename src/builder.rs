@@ -0,0 +1,640 @@
+use core::fmt;
+use core::ops::{Deref, DerefMut};
+
+use anyhow::{anyhow, Result};
+
+use plonky2::field::extension::Extendable;
+use plonky2::gates::arithmetic_base::ArithmeticGate;
+use plonky2::gates::gate::GateRef;
+use plonky2::hash::hash_types::RichField;
+use plonky2::iop::ext_target::ExtensionTarget;
+use plonky2::iop::target::{BoolTarget, Target};
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+use plonky2::plonk::circuit_data::{CircuitConfig, CircuitData};
+use plonky2::plonk::config::GenericConfig;
+
+use crate::analysis::estimate::{estimate_cost_with_pending, CostEstimate};
+use crate::gates::exp_var_gate::SimpleExpVarGate;
+use crate::gates::expression_gate::{Expression, ExpressionGate};
+use crate::gates::g_w_v_v_constant::SimpleExpConstantGate;
+use crate::gates::simple_add_ext_gate::SimpleMulAddExtensionGate;
+use crate::gates::simple_add_gate::SimpleMulAddTestGate;
+
+/// Helpers on `CircuitBuilder` which wire the cook gates for us.
+pub trait CookBuilderExt<F: RichField + Extendable<D>, const D: usize> {
+    /// Computes `a * b + c + d + e`.
+    /// The op is placed in a free slot of an existing `SimpleMulAddTestGate` row if there is one,
+    /// and a new row is only added when all `num_ops` slots of the current row are used.
+    fn mul_add3(&mut self, a: Target, b: Target, c: Target, d: Target, e: Target) -> Target;
+
+    /// The extension field version of `mul_add3`, using the slots of `SimpleMulAddExtensionGate` rows.
+    fn mul_add3_extension(
+        &mut self,
+        a: ExtensionTarget<D>,
+        b: ExtensionTarget<D>,
+        c: ExtensionTarget<D>,
+        d: ExtensionTarget<D>,
+        e: ExtensionTarget<D>,
+    ) -> ExtensionTarget<D>;
+
+    /// Computes `base^exponent` with a `SimpleExpVarGate`, where `exponent_bits` are the little-endian bits of the exponent.
+    fn exp_var(&mut self, base: Target, exponent_bits: &[BoolTarget]) -> Target;
+
+    /// Computes `expression` on `inputs` with an `ExpressionGate`.
+    /// Ops with the same expression and the same `constants` share rows.
+    fn expression_op(&mut self, expression: &Expression, inputs: &[Target], constants: &[F]) -> Target;
+
+    /// Computes `base^power` for a constant `base` and any `power`.
+    /// A `SimpleExpConstantGate` row computes at most `base^max_limbs`, and it always starts from `base^0 = 1`
+    /// with no input wire. So `power = q * max_limbs + r` uses one full row raised to `q` with `exp_u64`, and
+    /// one row of `r` limbs: two `SimpleExpConstantGate` rows and `O(log q)` `ArithmeticGate` ops.
+    fn exp_constant_base(&mut self, base: F, power: usize) -> Target;
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> CookBuilderExt<F, D> for CircuitBuilder<F, D> {
+    fn mul_add3(&mut self, a: Target, b: Target, c: Target, d: Target, e: Target) -> Target {
+        let gate = SimpleMulAddTestGate::new_from_config(&self.config);
+
+        // Same slot cache as plonky2's `ArithmeticGate`: `find_slot` hands out the next free op of the
+        // current row, and the unused ops of the last row are padded by `build`.
+        let (row, i) = self.find_slot(gate, &[], &[]);
+
+        self.connect(Target::wire(row, SimpleMulAddTestGate::wire_ith_multiplicand_0(i)), a);
+        self.connect(Target::wire(row, SimpleMulAddTestGate::wire_ith_multiplicand_1(i)), b);
+        self.connect(Target::wire(row, SimpleMulAddTestGate::wire_ith_add_1(i)), c);
+        self.connect(Target::wire(row, SimpleMulAddTestGate::wire_ith_add_2(i)), d);
+        self.connect(Target::wire(row, SimpleMulAddTestGate::wire_ith_addend(i)), e);
+
+        Target::wire(row, SimpleMulAddTestGate::wire_ith_output(i))
+    }
+
+    fn mul_add3_extension(
+        &mut self,
+        a: ExtensionTarget<D>,
+        b: ExtensionTarget<D>,
+        c: ExtensionTarget<D>,
+        d: ExtensionTarget<D>,
+        e: ExtensionTarget<D>,
+    ) -> ExtensionTarget<D> {
+        let gate = SimpleMulAddExtensionGate::<D>::new_from_config(&self.config);
+        let (row, i) = self.find_slot(gate, &[], &[]);
+
+        let operand = |range| ExtensionTarget::from_range(row, range);
+        self.connect_extension(operand(SimpleMulAddExtensionGate::<D>::wires_ith_multiplicand_0(i)), a);
+        self.connect_extension(operand(SimpleMulAddExtensionGate::<D>::wires_ith_multiplicand_1(i)), b);
+        self.connect_extension(operand(SimpleMulAddExtensionGate::<D>::wires_ith_add_1(i)), c);
+        self.connect_extension(operand(SimpleMulAddExtensionGate::<D>::wires_ith_add_2(i)), d);
+        self.connect_extension(operand(SimpleMulAddExtensionGate::<D>::wires_ith_addend(i)), e);
+
+        operand(SimpleMulAddExtensionGate::<D>::wires_ith_output(i))
+    }
+
+    fn exp_var(&mut self, base: Target, exponent_bits: &[BoolTarget]) -> Target {
+        assert!(
+            exponent_bits.len() <= SimpleExpVarGate::max_bits(&self.config),
+            "too many exponent bits for one `SimpleExpVarGate` row"
+        );
+
+        if exponent_bits.is_empty() {
+            return self.one();
+        }
+
+        let gate = SimpleExpVarGate::new(exponent_bits.len(), &self.config);
+        let row = self.add_gate(gate.clone(), vec![]);
+
+        self.connect(Target::wire(row, gate.wire_base()), base);
+        for (i, bit) in exponent_bits.iter().enumerate() {
+            self.connect(Target::wire(row, gate.wire_exponent_bit(i)), bit.target);
+        }
+
+        Target::wire(row, gate.wire_output())
+    }
+
+    fn expression_op(&mut self, expression: &Expression, inputs: &[Target], constants: &[F]) -> Target {
+        assert_eq!(inputs.len(), expression.inputs.len());
+        assert_eq!(constants.len(), expression.constants.len());
+
+        let gate = ExpressionGate::new_from_config(expression.clone(), &self.config);
+        let (row, i) = self.find_slot(gate.clone(), constants, constants);
+
+        for (j, &input) in inputs.iter().enumerate() {
+            self.connect(Target::wire(row, gate.wire_ith_input(i, j)), input);
+        }
+
+        Target::wire(row, gate.wire_ith_output(i))
+    }
+
+    fn exp_constant_base(&mut self, base: F, power: usize) -> Target {
+        // `SimpleExpConstantGate::new` requires the power to be strictly less than `max_power`
+        let max_limbs = SimpleExpConstantGate::max_power(&self.config) - 1;
+
+        let (full_rows, limbs) = (power / max_limbs, power % max_limbs);
+        let mut result = self.one();
+        if full_rows > 0 {
+            let gate = SimpleExpConstantGate::new(max_limbs, &self.config);
+            let row = self.add_gate(gate.clone(), vec![base]);
+            result = self.exp_u64(Target::wire(row, gate.wire_output()), full_rows as u64);
+        }
+        if limbs > 0 {
+            let gate = SimpleExpConstantGate::new(limbs, &self.config);
+            let row = self.add_gate(gate.clone(), vec![base]);
+            result = self.mul(result, Target::wire(row, gate.wire_output()));
+        }
+
+        result
+    }
+}
+
+/// A `mul` or `add` recorded by `FusingBuilder`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PendingOp {
+    Mul(Target, Target),
+    Add(Target, Target),
+}
+
+/// A `mul` and the `add`s fused with it, `operands` are `a, b` of the `mul` then the other operand of every `add`.
+#[derive(Debug, Clone)]
+struct FusedChain {
+    mul: usize,
+    adds: Vec<usize>,
+    operands: Vec<Target>,
+}
+
+/// What `FusingBuilder` saved.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FusionReport {
+    /// `mul` and `add` chains emitted as one `SimpleMulAddTestGate` op.
+    pub chains: usize,
+    /// The `ArithmeticGate` ops replaced by the chains.
+    pub arithmetic_ops_removed: usize,
+    /// `ceil(arithmetic_ops_removed / ArithmeticGate ops per row) - ceil(chains / SimpleMulAddTestGate ops per row)`,
+    /// exact when the fused ops do not share rows with others.
+    pub rows_saved: usize,
+    /// The rows of the circuit before `build` adds its constants and padding.
+    pub rows: usize,
+}
+
+impl fmt::Display for FusionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} chains fused, {} arithmetic ops removed, {} rows saved, {} rows",
+            self.chains, self.arithmetic_ops_removed, self.rows_saved, self.rows
+        )
+    }
+}
+
+/// An opt-in peephole pass over `mul` and `add`.
+///
+/// `mul` and `add` on this builder are recorded and return a placeholder target, every other method goes to
+/// the `CircuitBuilder`. `build` emits a `mul` followed by up to three `add`s as one `mul_add3` op when the
+/// intermediate results are only used by the next `add`, and the other ops as plonky2 would.
+/// An intermediate used by other builder methods can not be seen before `build`, so `build` fails if it
+/// finds one in the circuit: mark such targets with `keep`.
+pub struct FusingBuilder<F: RichField + Extendable<D>, const D: usize> {
+    builder: CircuitBuilder<F, D>,
+    ops: Vec<(PendingOp, Target)>,
+    kept: Vec<Target>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> FusingBuilder<F, D> {
+    pub fn new(config: CircuitConfig) -> Self {
+        Self {
+            builder: CircuitBuilder::new(config),
+            ops: Vec::new(),
+            kept: Vec::new(),
+        }
+    }
+
+    pub fn mul(&mut self, a: Target, b: Target) -> Target {
+        let output = self.builder.add_virtual_target();
+        self.ops.push((PendingOp::Mul(a, b), output));
+        output
+    }
+
+    pub fn add(&mut self, a: Target, b: Target) -> Target {
+        let output = self.builder.add_virtual_target();
+        self.ops.push((PendingOp::Add(a, b), output));
+        output
+    }
+
+    /// Keeps `target` out of any fused chain, because it is used outside of the recorded ops.
+    pub fn keep(&mut self, target: Target) {
+        self.kept.push(target);
+    }
+
+    /// The chains to fuse, in the order of their `mul`. An `add` joins at most one chain: in `x*y + z*w` only
+    /// the first `mul` is fused with the `add`, and the second one is emitted on its own.
+    fn chains(&self) -> Vec<FusedChain> {
+        let operands = |op: PendingOp| match op {
+            PendingOp::Mul(a, b) | PendingOp::Add(a, b) => [a, b],
+        };
+        let uses = |t: Target| self.ops.iter().flat_map(|&(op, _)| operands(op)).filter(|&o| o == t).count();
+
+        let mut claimed = vec![false; self.ops.len()];
+        let mut chains = Vec::new();
+        for (i, &(op, _)) in self.ops.iter().enumerate() {
+            let PendingOp::Mul(a, b) = op else {
+                continue;
+            };
+
+            let mut chain = FusedChain {
+                mul: i,
+                adds: Vec::new(),
+                operands: vec![a, b],
+            };
+            let mut last = i;
+            while chain.adds.len() < 3 {
+                let output = self.ops[last].1;
+                if uses(output) != 1 || self.kept.contains(&output) {
+                    break;
+                }
+                match self.ops.iter().position(|&(op, _)| operands(op).contains(&output)) {
+                    Some(j) if matches!(self.ops[j].0, PendingOp::Add(..)) && !claimed[j] => {
+                        let [x, y] = operands(self.ops[j].0);
+                        chain.operands.push(if x == output { y } else { x });
+                        chain.adds.push(j);
+                        last = j;
+                    }
+                    _ => break,
+                }
+            }
+            if !chain.adds.is_empty() {
+                claimed[i] = true;
+                for &j in chain.adds.iter() {
+                    claimed[j] = true;
+                }
+                chains.push(chain);
+            }
+        }
+        chains
+    }
+
+    /// The cost of the circuit if it were built now, with the recorded ops fused like `build` would.
+    pub fn estimate(&self) -> CostEstimate {
+        let config = &self.builder.config;
+        let chains = self.chains();
+        let fused: usize = chains.iter().map(|c| 1 + c.adds.len()).sum();
+        let arithmetic = ArithmeticGate::new_from_config(config);
+        let arithmetic_rows = (self.ops.len() - fused).div_ceil(arithmetic.num_ops);
+        let mul_add_rows = chains.len().div_ceil(SimpleMulAddTestGate::num_ops(config));
+        let pending = [
+            (GateRef::new(arithmetic), arithmetic_rows),
+            (GateRef::new(SimpleMulAddTestGate::new_from_config(config)), mul_add_rows),
+        ];
+        estimate_cost_with_pending(&self.builder, &pending)
+    }
+
+    /// Emits the recorded ops and builds the circuit, see the type documentation.
+    pub fn build<C: GenericConfig<D, F = F>>(mut self) -> Result<(CircuitData<F, C, D>, FusionReport)> {
+        let chains = self.chains();
+        let mut fused = vec![false; self.ops.len()];
+        // The outputs of every op of a chain but the last are never computed.
+        let mut intermediates = Vec::new();
+        for chain in chains.iter() {
+            fused[chain.mul] = true;
+            intermediates.push(self.ops[chain.mul].1);
+            for &j in chain.adds.iter() {
+                fused[j] = true;
+                intermediates.push(self.ops[j].1);
+            }
+            intermediates.pop();
+        }
+
+        // A chain is emitted at its last `add`, when all its operands exist.
+        for (i, &(op, output)) in self.ops.iter().enumerate() {
+            let result = match chains.iter().find(|c| c.adds.last() == Some(&i)) {
+                Some(chain) => {
+                    let zero = self.builder.zero();
+                    let o = |k: usize| chain.operands.get(k).copied().unwrap_or(zero);
+                    self.builder.mul_add3(o(0), o(1), o(2), o(3), o(4))
+                }
+                None if fused[i] => continue,
+                None => match op {
+                    PendingOp::Mul(a, b) => self.builder.mul(a, b),
+                    PendingOp::Add(a, b) => self.builder.add(a, b),
+                },
+            };
+            self.builder.connect(result, output);
+        }
+
+        let config = &self.builder.config;
+        let arithmetic_ops_removed = chains.iter().map(|c| 1 + c.adds.len()).sum();
+        let rows = |ops: usize, per_row: usize| ops.div_ceil(per_row);
+        let saved = rows(arithmetic_ops_removed, ArithmeticGate::new_from_config(config).num_ops);
+        let added = rows(chains.len(), SimpleMulAddTestGate::num_ops(config));
+        let report = FusionReport {
+            chains: chains.len(),
+            arithmetic_ops_removed,
+            rows_saved: saved.saturating_sub(added),
+            rows: self.builder.num_gates(),
+        };
+
+        let data = self.builder.build::<C>();
+
+        // A used intermediate is copied to a wire or another target, read by a generator or public.
+        let common = &data.common;
+        let (num_wires, degree) = (common.config.num_wires, common.degree());
+        let representative_map = &data.prover_only.representative_map;
+        let representative = |t: Target| representative_map[t.index(num_wires, degree)];
+        for &t in intermediates.iter() {
+            let r = representative(t);
+            let copied = representative_map.iter().filter(|&&other| other == r).count() > 1;
+            let read = data
+                .prover_only
+                .generators
+                .iter()
+                .any(|g| g.0.watch_list().iter().any(|&w| representative(w) == r));
+            let public = data.prover_only.public_inputs.iter().any(|&p| representative(p) == r);
+            if copied || read || public {
+                return Err(anyhow!("the fused intermediate {t:?} is used outside of its chain, `keep` it"));
+            }
+        }
+
+        Ok((data, report))
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> Deref for FusingBuilder<F, D> {
+    type Target = CircuitBuilder<F, D>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.builder
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> DerefMut for FusingBuilder<F, D> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.builder
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use anyhow::Result;
+
+    use plonky2::field::types::{Field, Sample};
+    use plonky2::iop::witness::{PartialWitness, WitnessWrite};
+    use plonky2::plonk::circuit_data::CircuitConfig;
+    use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+
+    use crate::analysis::mock_prover::{mock_prove, row_gates};
+
+    #[test]
+    fn mul_add3_shares_rows() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let num_ops = SimpleMulAddTestGate::num_ops(&config);
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let mut inputs = Vec::new();
+        let mut outputs = Vec::new();
+
+        let rows_before = builder.num_gates();
+        // One full row plus a single op in a second row.
+        for i in 0..(num_ops + 1) {
+            let targets: Vec<Target> = (0..5).map(|_| builder.add_virtual_target()).collect();
+            let output = builder.mul_add3(targets[0], targets[1], targets[2], targets[3], targets[4]);
+            inputs.push(targets);
+            outputs.push(output);
+
+            let expected_rows = if i < num_ops { 1 } else { 2 };
+            assert_eq!(builder.num_gates() - rows_before, expected_rows);
+        }
+
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        for (targets, &output) in inputs.iter().zip(outputs.iter()) {
+            let values = F::rand_vec(5);
+            for (&t, &v) in targets.iter().zip(values.iter()) {
+                pw.set_target(t, v);
+            }
+            pw.set_target(output, values[0] * values[1] + values[2] + values[3] + values[4]);
+        }
+
+        let proof = data.prove(pw)?;
+        data.verify(proof)
+    }
+
+    #[test]
+    fn exp_constant_base_multi_rows() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let max_limbs = SimpleExpConstantGate::max_power(&config) - 1;
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let base = F::from_canonical_u32(3);
+        // A single row, an exact number of rows, a partial last row and the power from the gate tests.
+        let powers = [0, 5, max_limbs, 2 * max_limbs, 2 * max_limbs + 7, 16000];
+        for &power in powers.iter() {
+            let output = builder.exp_constant_base(base, power);
+            builder.register_public_input(output);
+        }
+
+        let data = builder.build::<C>();
+        let proof = data.prove(PartialWitness::new())?;
+
+        for (&power, &output) in powers.iter().zip(proof.public_inputs.iter()) {
+            assert_eq!(output, base.exp_u64(power as u64));
+        }
+
+        data.verify(proof)
+    }
+
+    #[test]
+    fn exp_constant_base_rows() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        // 246 full rows of powers are one full row raised to 246, plus the row of the remaining limbs.
+        builder.exp_constant_base(F::TWO, 16000);
+        let data = builder.build::<C>();
+
+        let exp_rows = row_gates(&data)
+            .into_iter()
+            .filter(|&g| data.common.gates[g].0.id().starts_with("SimpleExpConstantGate"))
+            .count();
+        assert_eq!(exp_rows, 2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn mul_add3_wrong_output() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let targets: Vec<Target> = (0..5).map(|_| builder.add_virtual_target()).collect();
+        let output = builder.mul_add3(targets[0], targets[1], targets[2], targets[3], targets[4]);
+
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        for &t in targets.iter() {
+            pw.set_target(t, F::ONE);
+        }
+        pw.set_target(output, F::ONE);
+
+        let proof = data.prove(pw).unwrap();
+        data.verify(proof).unwrap();
+    }
+
+    const FD: usize = 2;
+    type FC = PoseidonGoldilocksConfig;
+    type FF = <FC as GenericConfig<FD>>::F;
+
+    /// `x * y + xx + yy + z` with the targets of `targets_test`, `chains` times, every output public.
+    fn plain_chains(chains: usize) -> (CircuitData<FF, FC, FD>, Vec<Vec<Target>>) {
+        let mut builder = CircuitBuilder::<FF, FD>::new(CircuitConfig::standard_recursion_config());
+        let mut inputs = Vec::new();
+        for _ in 0..chains {
+            let t: Vec<Target> = (0..5).map(|_| builder.add_virtual_target()).collect();
+            let xy = builder.mul(t[0], t[1]);
+            let sum = builder.add(xy, t[2]);
+            let sum = builder.add(sum, t[3]);
+            let output = builder.add(sum, t[4]);
+            builder.register_public_input(output);
+            inputs.push(t);
+        }
+        (builder.build::<FC>(), inputs)
+    }
+
+    fn fused_chains(chains: usize) -> Result<(CircuitData<FF, FC, FD>, FusionReport, Vec<Vec<Target>>)> {
+        let mut builder = FusingBuilder::<FF, FD>::new(CircuitConfig::standard_recursion_config());
+        let mut inputs = Vec::new();
+        for _ in 0..chains {
+            let t: Vec<Target> = (0..5).map(|_| builder.add_virtual_target()).collect();
+            let xy = builder.mul(t[0], t[1]);
+            let sum = builder.add(xy, t[2]);
+            let sum = builder.add(sum, t[3]);
+            let output = builder.add(sum, t[4]);
+            builder.register_public_input(output);
+            inputs.push(t);
+        }
+        let (data, report) = builder.build::<FC>()?;
+        Ok((data, report, inputs))
+    }
+
+    fn witness(inputs: &[Vec<Target>], values: &[FF]) -> PartialWitness<FF> {
+        let mut pw = PartialWitness::new();
+        for (&t, &v) in inputs.iter().flatten().zip(values.iter()) {
+            pw.set_target(t, v);
+        }
+        pw
+    }
+
+    #[test]
+    fn fuse_targets_test_chain() -> Result<()> {
+        let (data, report, inputs) = fused_chains(1)?;
+        assert_eq!(report.chains, 1);
+        assert_eq!(report.arithmetic_ops_removed, 4);
+
+        let proof = data.prove(witness(&inputs, &FF::rand_vec(5)))?;
+        data.verify(proof)
+    }
+
+    #[test]
+    fn fused_same_witnesses() -> Result<()> {
+        let (plain, plain_inputs) = plain_chains(3);
+        let (fused, _, fused_inputs) = fused_chains(3)?;
+
+        let values = FF::rand_vec(15);
+        let plain_proof = plain.prove(witness(&plain_inputs, &values))?;
+        let fused_proof = fused.prove(witness(&fused_inputs, &values))?;
+        assert_eq!(plain_proof.public_inputs, fused_proof.public_inputs);
+
+        // A wrong output is rejected by both.
+        let wrong = plain_proof.public_inputs[0] + FF::ONE;
+        let mut pw = witness(&plain_inputs, &values);
+        pw.set_target(plain.prover_only.public_inputs[0], wrong);
+        assert!(mock_prove(&plain, pw).is_err());
+        let mut pw = witness(&fused_inputs, &values);
+        pw.set_target(fused.prover_only.public_inputs[0], wrong);
+        assert!(mock_prove(&fused, pw).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn fused_rows_saved() -> Result<()> {
+        let chains = 20;
+        let (plain, _) = plain_chains(chains);
+        let (fused, report, _) = fused_chains(chains)?;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let plain_rows = (4 * chains).div_ceil(ArithmeticGate::new_from_config(&config).num_ops);
+        let fused_rows = chains.div_ceil(SimpleMulAddTestGate::num_ops(&config));
+        assert_eq!(report.rows_saved, plain_rows - fused_rows);
+        assert!(report.rows_saved > 0);
+        assert_eq!(row_count(&plain) - row_count(&fused), report.rows_saved);
+        Ok(())
+    }
+
+    /// The rows of `data` which are not padding.
+    fn row_count(data: &CircuitData<FF, FC, FD>) -> usize {
+        let gates = row_gates(data);
+        gates.iter().filter(|&&g| !data.common.gates[g].0.id().starts_with("NoopGate")).count()
+    }
+
+    #[test]
+    fn fused_intermediate_escapes() -> Result<()> {
+        let build = |keep: bool| {
+            let mut builder = FusingBuilder::<FF, FD>::new(CircuitConfig::standard_recursion_config());
+            let t: Vec<Target> = (0..3).map(|_| builder.add_virtual_target()).collect();
+            let xy = builder.mul(t[0], t[1]);
+            let output = builder.add(xy, t[2]);
+            builder.register_public_input(output);
+            // `xy` is used by a builder method the pass does not see.
+            builder.register_public_input(xy);
+            if keep {
+                builder.keep(xy);
+            }
+            (builder.build::<FC>(), t)
+        };
+
+        assert!(build(false).0.is_err());
+        let (data, inputs) = build(true);
+        let (data, report) = data?;
+        assert_eq!(report.chains, 0);
+
+        let values = FF::rand_vec(3);
+        let proof = data.prove(witness(&[inputs], &values))?;
+        assert_eq!(proof.public_inputs[1], values[0] * values[1]);
+        data.verify(proof)
+    }
+
+    #[test]
+    fn fused_sum_of_products() -> Result<()> {
+        // `x*y + z*w`: both products are only used by the `add`, which can only join one chain.
+        let mut builder = FusingBuilder::<FF, FD>::new(CircuitConfig::standard_recursion_config());
+        let t: Vec<Target> = (0..4).map(|_| builder.add_virtual_target()).collect();
+        let xy = builder.mul(t[0], t[1]);
+        let zw = builder.mul(t[2], t[3]);
+        let output = builder.add(xy, zw);
+        builder.register_public_input(output);
+
+        let (data, report) = builder.build::<FC>()?;
+        assert_eq!(report.chains, 1);
+        assert_eq!(report.arithmetic_ops_removed, 2);
+
+        let values = FF::rand_vec(4);
+        let proof = data.prove(witness(&[t], &values))?;
+        assert_eq!(proof.public_inputs[0], values[0] * values[1] + values[2] * values[3]);
+        data.verify(proof)
+    }
+}
@@ -0,0 +1,314 @@
+//! A versioned file format for a `ProofTuple`.
+//!
+//! ```text
+//! magic            8 bytes   b"COOKPRF\0"
+//! version          u32 LE
+//! config id        u64 LE length + UTF-8, `BundleConfig::ID` of the `GenericConfig`
+//! extension degree u32 LE
+//! proof            u64 LE length + `ProofWithPublicInputs::to_bytes`
+//! verifier only    u64 LE length + `VerifierOnlyCircuitData::to_bytes`
+//! common           u64 LE length + `CommonCircuitData::to_bytes` with `CookGateSerializer`
+//! checksum         32 bytes, Keccak256 of everything above
+//! ```
+
+use core::fmt;
+use std::fs;
+use std::path::Path;
+
+use sha3::{Digest, Keccak256};
+
+use plonky2::field::extension::Extendable;
+use plonky2::hash::hash_types::RichField;
+use plonky2::plonk::circuit_data::{CommonCircuitData, VerifierOnlyCircuitData};
+use plonky2::plonk::config::{GenericConfig, KeccakGoldilocksConfig, PoseidonGoldilocksConfig};
+use plonky2::plonk::proof::ProofWithPublicInputs;
+use plonky2_ecdsa::gadgets::recursive_proof::ProofTuple;
+
+use crate::serialization::CookGateSerializer;
+
+pub const BUNDLE_MAGIC: [u8; 8] = *b"COOKPRF\0";
+/// Version 1 wrote the Rust type name of the config, which is not stable across compilers.
+pub const BUNDLE_VERSION: u32 = 2;
+
+const CHECKSUM_LEN: usize = 32;
+
+#[derive(Debug)]
+pub enum BundleError {
+    Io(std::io::Error),
+    /// The input ends inside `section`.
+    Truncated { section: &'static str },
+    BadMagic,
+    UnsupportedVersion(u32),
+    /// The bundle was written with another `GenericConfig`.
+    ConfigMismatch { expected: String, found: String },
+    ExtensionDegreeMismatch { expected: usize, found: usize },
+    ChecksumMismatch,
+    /// `section` could not be serialized.
+    Serialize { section: &'static str },
+    /// `section` has the right length but can not be deserialized.
+    Corrupted { section: &'static str },
+    /// There are bytes after the checksum.
+    TrailingBytes(usize),
+}
+
+impl fmt::Display for BundleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BundleError::Io(e) => write!(f, "{e}"),
+            BundleError::Truncated { section } => write!(f, "the bundle is truncated in the {section}"),
+            BundleError::BadMagic => write!(f, "not a proof bundle"),
+            BundleError::UnsupportedVersion(v) => {
+                write!(f, "unsupported bundle version {v}, expected {BUNDLE_VERSION}")
+            }
+            BundleError::ConfigMismatch { expected, found } => {
+                write!(f, "the bundle uses the config {found}, expected {expected}")
+            }
+            BundleError::ExtensionDegreeMismatch { expected, found } => {
+                write!(f, "the bundle uses the extension degree {found}, expected {expected}")
+            }
+            BundleError::ChecksumMismatch => write!(f, "the bundle checksum does not match"),
+            BundleError::Serialize { section } => write!(f, "cannot serialize the {section}"),
+            BundleError::Corrupted { section } => write!(f, "the {section} of the bundle is corrupted"),
+            BundleError::TrailingBytes(n) => write!(f, "{n} unexpected bytes after the bundle"),
+        }
+    }
+}
+
+impl std::error::Error for BundleError {}
+
+impl From<std::io::Error> for BundleError {
+    fn from(e: std::io::Error) -> Self {
+        BundleError::Io(e)
+    }
+}
+
+/// A `GenericConfig` which can be written to a bundle, with an id which never changes.
+pub trait BundleConfig {
+    const ID: &'static str;
+}
+
+impl BundleConfig for PoseidonGoldilocksConfig {
+    const ID: &'static str = "poseidon-goldilocks";
+}
+
+impl BundleConfig for KeccakGoldilocksConfig {
+    const ID: &'static str = "keccak-goldilocks";
+}
+
+fn write_section(dst: &mut Vec<u8>, bytes: &[u8]) {
+    dst.extend_from_slice(&(bytes.len() as u64).to_le_bytes());
+    dst.extend_from_slice(bytes);
+}
+
+/// Reads the bundle front to back, naming the section it is in for `BundleError::Truncated`.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize, section: &'static str) -> Result<&'a [u8], BundleError> {
+        if self.bytes.len() - self.pos < len {
+            return Err(BundleError::Truncated { section });
+        }
+        let bytes = &self.bytes[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn read_u32(&mut self, section: &'static str) -> Result<u32, BundleError> {
+        Ok(u32::from_le_bytes(self.take(4, section)?.try_into().unwrap()))
+    }
+
+    fn read_section(&mut self, section: &'static str) -> Result<&'a [u8], BundleError> {
+        let len = u64::from_le_bytes(self.take(8, section)?.try_into().unwrap());
+        let len = usize::try_from(len).map_err(|_| BundleError::Truncated { section })?;
+        self.take(len, section)
+    }
+}
+
+pub fn write_proof_bundle<F: RichField + Extendable<D>, C: GenericConfig<D, F = F> + BundleConfig, const D: usize>(
+    tuple: &ProofTuple<F, C, D>,
+) -> Result<Vec<u8>, BundleError> {
+    let (proof, verifier_only, common) = tuple;
+
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(&BUNDLE_MAGIC);
+    bytes.extend_from_slice(&BUNDLE_VERSION.to_le_bytes());
+    write_section(&mut bytes, C::ID.as_bytes());
+    bytes.extend_from_slice(&(D as u32).to_le_bytes());
+
+    write_section(&mut bytes, &proof.to_bytes());
+    let verifier_only = verifier_only
+        .to_bytes()
+        .map_err(|_| BundleError::Serialize { section: "verifier only data" })?;
+    write_section(&mut bytes, &verifier_only);
+    let common = common
+        .to_bytes(&CookGateSerializer)
+        .map_err(|_| BundleError::Serialize { section: "common data" })?;
+    write_section(&mut bytes, &common);
+
+    let checksum = Keccak256::digest(&bytes);
+    bytes.extend_from_slice(&checksum);
+
+    Ok(bytes)
+}
+
+pub fn read_proof_bundle<F: RichField + Extendable<D>, C: GenericConfig<D, F = F> + BundleConfig, const D: usize>(
+    bytes: &[u8],
+) -> Result<ProofTuple<F, C, D>, BundleError> {
+    let mut reader = Reader { bytes, pos: 0 };
+
+    if reader.take(BUNDLE_MAGIC.len(), "header")? != BUNDLE_MAGIC {
+        return Err(BundleError::BadMagic);
+    }
+    let version = reader.read_u32("header")?;
+    if version != BUNDLE_VERSION {
+        return Err(BundleError::UnsupportedVersion(version));
+    }
+    let found = String::from_utf8_lossy(reader.read_section("header")?).into_owned();
+    if found != C::ID {
+        return Err(BundleError::ConfigMismatch {
+            expected: C::ID.to_string(),
+            found,
+        });
+    }
+    let degree = reader.read_u32("header")? as usize;
+    if degree != D {
+        return Err(BundleError::ExtensionDegreeMismatch { expected: D, found: degree });
+    }
+
+    let proof = reader.read_section("proof")?;
+    let verifier_only = reader.read_section("verifier only data")?;
+    let common = reader.read_section("common data")?;
+
+    let end = reader.pos;
+    if reader.take(CHECKSUM_LEN, "checksum")? != Keccak256::digest(&bytes[..end]).as_slice() {
+        return Err(BundleError::ChecksumMismatch);
+    }
+    if reader.pos != bytes.len() {
+        return Err(BundleError::TrailingBytes(bytes.len() - reader.pos));
+    }
+
+    let common = CommonCircuitData::<F, D>::from_bytes(common.to_vec(), &CookGateSerializer)
+        .map_err(|_| BundleError::Corrupted { section: "common data" })?;
+    let verifier_only = VerifierOnlyCircuitData::<C, D>::from_bytes(verifier_only.to_vec())
+        .map_err(|_| BundleError::Corrupted { section: "verifier only data" })?;
+    let proof = ProofWithPublicInputs::<F, C, D>::from_bytes(proof.to_vec(), &common)
+        .map_err(|_| BundleError::Corrupted { section: "proof" })?;
+
+    Ok((proof, verifier_only, common))
+}
+
+pub fn save_proof_bundle<F: RichField + Extendable<D>, C: GenericConfig<D, F = F> + BundleConfig, const D: usize>(
+    path: &Path,
+    tuple: &ProofTuple<F, C, D>,
+) -> Result<(), BundleError> {
+    fs::write(path, write_proof_bundle(tuple)?)?;
+    Ok(())
+}
+
+pub fn load_proof_bundle<F: RichField + Extendable<D>, C: GenericConfig<D, F = F> + BundleConfig, const D: usize>(
+    path: &Path,
+) -> Result<ProofTuple<F, C, D>, BundleError> {
+    read_proof_bundle(&fs::read(path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use anyhow::Result;
+
+    use plonky2::plonk::circuit_data::VerifierCircuitData;
+
+    use crate::examples::Example;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    fn array_sum_bundle() -> Result<Vec<u8>> {
+        let circuit = Example::ArraySum.build::<F, C, D>(3)?;
+        let values: Vec<(String, u64)> = (0..3).map(|i| (format!("x{i}"), i + 1)).collect();
+        let proof = circuit.data.prove(circuit.witness(&values)?)?;
+
+        Ok(write_proof_bundle(&(proof, circuit.data.verifier_only, circuit.data.common))?)
+    }
+
+    #[test]
+    fn round_trip() -> Result<()> {
+        let bytes = array_sum_bundle()?;
+        // The header holds the stable id, not a compiler generated name.
+        let id_pos = BUNDLE_MAGIC.len() + 4 + 8;
+        assert_eq!(&bytes[id_pos..id_pos + C::ID.len()], b"poseidon-goldilocks");
+        let (proof, verifier_only, common) = read_proof_bundle::<F, C, D>(&bytes)?;
+
+        VerifierCircuitData { verifier_only, common }.verify(proof)
+    }
+
+    #[test]
+    fn truncated() -> Result<()> {
+        let bytes = array_sum_bundle()?;
+
+        assert!(matches!(
+            read_proof_bundle::<F, C, D>(&bytes[..6]),
+            Err(BundleError::Truncated { section: "header" })
+        ));
+        assert!(matches!(
+            read_proof_bundle::<F, C, D>(&bytes[..bytes.len() - CHECKSUM_LEN - 10]),
+            Err(BundleError::Truncated { section: "common data" })
+        ));
+        assert!(matches!(
+            read_proof_bundle::<F, C, D>(&bytes[..bytes.len() - 1]),
+            Err(BundleError::Truncated { section: "checksum" })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn mismatched() -> Result<()> {
+        let bytes = array_sum_bundle()?;
+
+        let mut wrong_magic = bytes.clone();
+        wrong_magic[0] ^= 1;
+        assert!(matches!(read_proof_bundle::<F, C, D>(&wrong_magic), Err(BundleError::BadMagic)));
+
+        let mut wrong_version = bytes.clone();
+        wrong_version[BUNDLE_MAGIC.len()] = 1;
+        assert!(matches!(
+            read_proof_bundle::<F, C, D>(&wrong_version),
+            Err(BundleError::UnsupportedVersion(1))
+        ));
+
+        assert!(matches!(
+            read_proof_bundle::<F, KeccakGoldilocksConfig, D>(&bytes),
+            Err(BundleError::ConfigMismatch { .. })
+        ));
+
+        // The extension degree comes right after the config id.
+        let mut wrong_degree = bytes.clone();
+        let degree_pos = BUNDLE_MAGIC.len() + 4 + 8 + C::ID.len();
+        wrong_degree[degree_pos] = 4;
+        assert!(matches!(
+            read_proof_bundle::<F, C, D>(&wrong_degree),
+            Err(BundleError::ExtensionDegreeMismatch { expected: 2, found: 4 })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn corrupted() -> Result<()> {
+        let bytes = array_sum_bundle()?;
+
+        let mut flipped = bytes.clone();
+        let middle = bytes.len() / 2;
+        flipped[middle] ^= 1;
+        assert!(matches!(read_proof_bundle::<F, C, D>(&flipped), Err(BundleError::ChecksumMismatch)));
+
+        let mut trailing = bytes;
+        trailing.push(0);
+        assert!(matches!(read_proof_bundle::<F, C, D>(&trailing), Err(BundleError::TrailingBytes(1))));
+        Ok(())
+    }
+}
@@ -0,0 +1,307 @@
+use core::fmt;
+use core::str::FromStr;
+
+use anyhow::{anyhow, Result};
+
+use plonky2::field::extension::Extendable;
+use plonky2::field::types::Field;
+use plonky2::hash::hash_types::{HashOutTarget, MerkleCapTarget, RichField};
+use plonky2::hash::merkle_proofs::MerkleProofTarget;
+use plonky2::hash::merkle_tree::MerkleTree;
+use plonky2::hash::poseidon::PoseidonHash;
+use plonky2::iop::target::Target;
+use plonky2::iop::witness::{PartialWitness, WitnessWrite};
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+use plonky2::plonk::circuit_data::{CircuitConfig, CircuitData};
+use plonky2::plonk::config::GenericConfig;
+
+use crate::builder::CookBuilderExt;
+use crate::gates::gate_with_veriable_vars::SimpleExpTestGate;
+
+/// The example circuits of the `cook` binary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Example {
+    /// `sum = x0 + ... + x{size-1}`
+    ArraySum,
+    /// `size` independent `out{k} = a{k} * b{k} + c{k} + d{k} + e{k}` with `CookBuilderExt::mul_add3`
+    MulAdd,
+    /// `base^size` with a `SimpleExpTestGate`
+    Exp,
+    /// `2^size` with `CookBuilderExt::exp_constant_base`, it has no inputs
+    ConstantExp,
+    /// `leaf` is at `index` of a Poseidon Merkle tree of height `size`, whose root is public.
+    /// The other leaves are their own index, so the witness only needs `leaf` and `index`.
+    MerkleMembership,
+}
+
+impl Example {
+    pub const ALL: [Example; 5] = [
+        Example::ArraySum,
+        Example::MulAdd,
+        Example::Exp,
+        Example::ConstantExp,
+        Example::MerkleMembership,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Example::ArraySum => "array-sum",
+            Example::MulAdd => "mul-add",
+            Example::Exp => "exp",
+            Example::ConstantExp => "constant-exp",
+            Example::MerkleMembership => "merkle-membership",
+        }
+    }
+
+    pub fn default_size(&self) -> usize {
+        match self {
+            Example::ArraySum => 3,
+            Example::MulAdd => 1,
+            Example::Exp => 8,
+            Example::ConstantExp => 8,
+            Example::MerkleMembership => 4,
+        }
+    }
+
+    pub fn build<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+        &self,
+        size: usize,
+    ) -> Result<ExampleCircuit<F, C, D>> {
+        Ok(self.builder::<F, D>(size)?.build::<C>())
+    }
+
+    /// The example before `build`, e.g. to estimate its cost.
+    pub fn builder<F: RichField + Extendable<D>, const D: usize>(&self, size: usize) -> Result<ExampleBuilder<F, D>> {
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config.clone());
+        let mut inputs = Vec::new();
+        let mut merkle = None;
+
+        match self {
+            Example::ArraySum => {
+                let targets: Vec<Target> = (0..size).map(|_| builder.add_virtual_target()).collect();
+                let sum = builder.add_many(&targets);
+                builder.register_public_input(sum);
+                inputs.extend(targets.into_iter().enumerate().map(|(i, t)| (format!("x{i}"), t)));
+            }
+            Example::MulAdd => {
+                for k in 0..size {
+                    let operands: Vec<Target> = (0..5).map(|_| builder.add_virtual_target()).collect();
+                    let out = builder.mul_add3(operands[0], operands[1], operands[2], operands[3], operands[4]);
+                    builder.register_public_input(out);
+                    inputs.extend(["a", "b", "c", "d", "e"].iter().zip(operands).map(|(n, t)| (format!("{n}{k}"), t)));
+                }
+            }
+            Example::Exp => {
+                // The degree of the gate is the power, plonky2 panics in `build` above `max_quotient_degree_factor`.
+                let max_power = config.max_quotient_degree_factor.min(SimpleExpTestGate::max_power(&config) - 1);
+                if size == 0 || size > max_power {
+                    return Err(anyhow!("`exp` needs a power between 1 and {max_power}"));
+                }
+                let gate = SimpleExpTestGate::new(size, &config);
+                let row = builder.add_gate(gate.clone(), vec![]);
+                let base = builder.add_virtual_target();
+                builder.connect(base, Target::wire(row, gate.wire_base()));
+                builder.register_public_input(Target::wire(row, gate.wire_output()));
+                inputs.push(("base".to_string(), base));
+            }
+            Example::ConstantExp => {
+                let out = builder.exp_constant_base(F::TWO, size);
+                builder.register_public_input(out);
+            }
+            Example::MerkleMembership => {
+                if size == 0 || size > 20 {
+                    return Err(anyhow!("`merkle-membership` needs a height between 1 and 20"));
+                }
+                let leaf = builder.add_virtual_target();
+                let index = builder.add_virtual_target();
+                let index_bits = builder.split_le(index, size);
+                let siblings: Vec<HashOutTarget> = (0..size).map(|_| builder.add_virtual_hash()).collect();
+                let root = builder.add_virtual_hash();
+                builder.register_public_inputs(&root.elements);
+
+                builder.verify_merkle_proof_to_cap::<PoseidonHash>(
+                    vec![leaf],
+                    &index_bits,
+                    &MerkleCapTarget(vec![root]),
+                    &MerkleProofTarget { siblings: siblings.clone() },
+                );
+
+                inputs.push(("leaf".to_string(), leaf));
+                inputs.push(("index".to_string(), index));
+                merkle = Some(MerkleTargets { siblings, root });
+            }
+        }
+
+        Ok(ExampleBuilder {
+            example: *self,
+            size,
+            builder,
+            inputs,
+            merkle,
+        })
+    }
+}
+
+impl fmt::Display for Example {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Example {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Example::ALL.into_iter().find(|e| e.name() == s).ok_or_else(|| {
+            let names: Vec<&str> = Example::ALL.iter().map(|e| e.name()).collect();
+            anyhow!("unknown example `{s}`, expected one of {}", names.join(", "))
+        })
+    }
+}
+
+/// The targets of `Example::MerkleMembership` which are filled from the native tree.
+struct MerkleTargets {
+    siblings: Vec<HashOutTarget>,
+    root: HashOutTarget,
+}
+
+pub struct ExampleBuilder<F: RichField + Extendable<D>, const D: usize> {
+    pub example: Example,
+    pub size: usize,
+    pub builder: CircuitBuilder<F, D>,
+    pub inputs: Vec<(String, Target)>,
+    merkle: Option<MerkleTargets>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> ExampleBuilder<F, D> {
+    pub fn build<C: GenericConfig<D, F = F>>(self) -> ExampleCircuit<F, C, D> {
+        ExampleCircuit {
+            example: self.example,
+            size: self.size,
+            data: self.builder.build::<C>(),
+            inputs: self.inputs,
+            merkle: self.merkle,
+        }
+    }
+}
+
+pub struct ExampleCircuit<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> {
+    pub example: Example,
+    pub size: usize,
+    pub data: CircuitData<F, C, D>,
+    /// The named inputs a witness has to set.
+    pub inputs: Vec<(String, Target)>,
+    merkle: Option<MerkleTargets>,
+}
+
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> ExampleCircuit<F, C, D> {
+    /// Sets every named input from `values`, all of them are needed and no others are accepted.
+    pub fn witness(&self, values: &[(String, u64)]) -> Result<PartialWitness<F>> {
+        if let Some((name, _)) = values.iter().find(|(n, _)| self.inputs.iter().all(|(i, _)| i != n)) {
+            return Err(anyhow!("`{}` is not an input of `{}`", name, self.example));
+        }
+
+        let mut pw = PartialWitness::new();
+        let mut named = Vec::with_capacity(self.inputs.len());
+        for (name, target) in self.inputs.iter() {
+            let value = values
+                .iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, v)| *v)
+                .ok_or_else(|| anyhow!("missing input `{}` of `{}`", name, self.example))?;
+            if value >= F::ORDER {
+                return Err(anyhow!("`{}` = {} is not below the field order", name, value));
+            }
+            let value = F::from_canonical_u64(value);
+            pw.set_target(*target, value);
+            named.push(value);
+        }
+
+        if let Some(merkle) = &self.merkle {
+            // `inputs` are `leaf` then `index`
+            let index = values.iter().find(|(n, _)| n == "index").map(|(_, v)| *v).unwrap_or_default();
+            if index >= 1 << self.size {
+                return Err(anyhow!("`index` must be less than {}", 1u64 << self.size));
+            }
+
+            let mut leaves: Vec<Vec<F>> = (0..1usize << self.size).map(|j| vec![F::from_canonical_usize(j)]).collect();
+            leaves[index as usize] = vec![named[0]];
+            let tree = MerkleTree::<F, PoseidonHash>::new(leaves, 0);
+
+            for (t, hash) in merkle.siblings.iter().zip(tree.prove(index as usize).siblings) {
+                pw.set_hash_target(*t, hash);
+            }
+            pw.set_hash_target(merkle.root, tree.cap.0[0]);
+        }
+
+        Ok(pw)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use plonky2::plonk::config::PoseidonGoldilocksConfig;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    #[test]
+    fn prove_every_example() -> Result<()> {
+        for example in Example::ALL {
+            let circuit = example.build::<F, C, D>(example.default_size())?;
+            let values: Vec<(String, u64)> = circuit
+                .inputs
+                .iter()
+                .enumerate()
+                .map(|(i, (name, _))| (name.clone(), i as u64 + 1))
+                .collect();
+
+            let proof = circuit.data.prove(circuit.witness(&values)?)?;
+            circuit.data.verify(proof)?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn exp_output() -> Result<()> {
+        let circuit = Example::Exp.build::<F, C, D>(5)?;
+        let proof = circuit.data.prove(circuit.witness(&[("base".to_string(), 3)])?)?;
+        assert_eq!(proof.public_inputs, vec![F::from_canonical_u64(243)]);
+        Ok(())
+    }
+
+    #[test]
+    fn exp_sizes() -> Result<()> {
+        let config = CircuitConfig::standard_recursion_config();
+        Example::Exp.build::<F, C, D>(config.max_quotient_degree_factor)?;
+        assert!(Example::Exp.builder::<F, D>(config.max_quotient_degree_factor + 1).is_err());
+        assert!(Example::Exp.builder::<F, D>(0).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn bad_witness() -> Result<()> {
+        let circuit = Example::MerkleMembership.build::<F, C, D>(2)?;
+        let value = |name: &str, v: u64| (name.to_string(), v);
+
+        assert!(circuit.witness(&[value("leaf", 7)]).is_err());
+        assert!(circuit.witness(&[value("leaf", 7), value("index", 4)]).is_err());
+        assert!(circuit.witness(&[value("leaf", 7), value("index", 1), value("x", 1)]).is_err());
+        assert!(circuit.witness(&[value("leaf", 7), value("index", 1)]).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn names() -> Result<()> {
+        for example in Example::ALL {
+            assert_eq!(example.name().parse::<Example>()?, example);
+        }
+        assert!("array_sum".parse::<Example>().is_err());
+        Ok(())
+    }
+}
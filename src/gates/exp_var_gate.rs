@@ -0,0 +1,380 @@
+#![allow(incomplete_features)]
+
+use core::marker::PhantomData;
+
+use plonky2::field::extension::Extendable;
+use plonky2::field::packed::PackedField;
+use plonky2::gates::gate::Gate;
+use plonky2::gates::packed_util::PackedEvaluableBase;
+use plonky2::gates::util::StridedConstraintConsumer;
+use plonky2::hash::hash_types::RichField;
+use plonky2::iop::ext_target::ExtensionTarget;
+use plonky2::iop::generator::{GeneratedValues, SimpleGenerator, WitnessGeneratorRef};
+use plonky2::iop::target::Target;
+use plonky2::iop::witness::{PartitionWitness, Witness, WitnessWrite};
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+use plonky2::plonk::circuit_data::{CircuitConfig, CommonCircuitData};
+use plonky2::plonk::vars::{
+    EvaluationTargets, EvaluationVars, EvaluationVarsBase, EvaluationVarsBaseBatch,
+    EvaluationVarsBasePacked,
+};
+use plonky2::util::serialization::{Buffer, IoResult, Read, Write};
+
+/// A gate proving `output = base^exponent`, where the exponent is a witness given by its bits.
+/// The exponent is processed from the most significant bit with square-and-multiply:
+/// `acc_j = acc_{j-1}^2 * (bit * base + 1 - bit)`, and every `acc_j` has its own wire.
+#[derive(Debug, Clone, Default)]
+pub struct SimpleExpVarGate {
+    pub num_bits: usize
+}
+
+impl SimpleExpVarGate {
+    pub fn new(num_bits: usize, config: &CircuitConfig) -> Self {
+        debug_assert!(num_bits > 0);
+        debug_assert!(num_bits <= Self::max_bits(config));
+
+        Self {
+            num_bits,
+        }
+    }
+
+    /// Determine the maximum number of exponent bits that can fit in one gate for the given config.
+    pub fn max_bits(config: &CircuitConfig) -> usize {
+        // `base`, `output` and the bits are routed, the accumulators can use the advice wires as well.
+        let max_for_routed_wires = config.num_routed_wires - 2;
+        let max_for_wires = (config.num_wires - 2) / 2;
+        max_for_routed_wires.min(max_for_wires)
+    }
+
+    pub fn wire_base(&self) -> usize {
+        0
+    }
+
+    /// The `i`th bit of the exponent, in little-endian order.
+    pub fn wire_exponent_bit(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_bits);
+        1 + i
+    }
+
+    pub fn wire_output(&self) -> usize {
+        1 + self.num_bits
+    }
+
+    /// The accumulator after the `j`th step, the steps go from the most significant bit down.
+    pub fn wire_intermediate_value(&self, j: usize) -> usize {
+        debug_assert!(j < self.num_bits);
+        2 + self.num_bits + j
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> Gate<F, D> for SimpleExpVarGate {
+    fn id(&self) -> String {
+        format!("{self:?}")
+    }
+
+    fn serialize(&self, dst: &mut Vec<u8>, _common_data: &CommonCircuitData<F, D>) -> IoResult<()> {
+        dst.write_usize(self.num_bits)
+    }
+
+    fn deserialize(src: &mut Buffer, _common_data: &CommonCircuitData<F, D>) -> IoResult<Self> {
+        let num_bits = src.read_usize()?;
+        Ok(Self { num_bits })
+    }
+
+    fn eval_unfiltered(&self, vars: EvaluationVars<F, D>) -> Vec<F::Extension> {
+        let mut constraints = Vec::with_capacity(2 * self.num_bits + 1);
+
+        let one: F::Extension = F::ONE.into();
+        let v_base = vars.local_wires[self.wire_base()];
+
+        let mut pre_acc = one;
+        for j in 0..self.num_bits {
+            let bit = vars.local_wires[self.wire_exponent_bit(self.num_bits - 1 - j)];
+            constraints.push(bit * (bit - one));
+
+            let multiplier = bit * v_base + one - bit;
+            let acc = vars.local_wires[self.wire_intermediate_value(j)];
+            constraints.push(acc - pre_acc * pre_acc * multiplier);
+
+            pre_acc = acc;
+        }
+
+        let output = vars.local_wires[self.wire_output()];
+        constraints.push(output - pre_acc);
+
+        constraints
+    }
+
+    fn eval_unfiltered_base_one(
+        &self,
+        _vars: EvaluationVarsBase<F>,
+        _yield_constr: StridedConstraintConsumer<F>,
+    ) {
+        panic!("use eval_unfiltered_base_packed instead");
+    }
+
+    fn eval_unfiltered_base_batch(&self, vars_base: EvaluationVarsBaseBatch<F>) -> Vec<F> {
+        self.eval_unfiltered_base_batch_packed(vars_base)
+    }
+
+    fn eval_unfiltered_circuit(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: EvaluationTargets<D>,
+    ) -> Vec<ExtensionTarget<D>> {
+        let mut constraints = Vec::with_capacity(2 * self.num_bits + 1);
+
+        let one = builder.one_extension();
+        let v_base = vars.local_wires[self.wire_base()];
+        let base_minus_one = builder.add_const_extension(v_base, F::NEG_ONE);
+
+        let mut pre_acc = one;
+        for j in 0..self.num_bits {
+            let bit = vars.local_wires[self.wire_exponent_bit(self.num_bits - 1 - j)];
+            constraints.push(builder.mul_sub_extension(bit, bit, bit));
+
+            // `bit * base + 1 - bit` == `bit * (base - 1) + 1`
+            let multiplier = builder.mul_add_extension(bit, base_minus_one, one);
+            let acc = vars.local_wires[self.wire_intermediate_value(j)];
+            let computed_acc = {
+                let squared = builder.square_extension(pre_acc);
+                builder.mul_extension(squared, multiplier)
+            };
+            constraints.push(builder.sub_extension(acc, computed_acc));
+
+            pre_acc = acc;
+        }
+
+        let output = vars.local_wires[self.wire_output()];
+        constraints.push(builder.sub_extension(output, pre_acc));
+
+        constraints
+    }
+
+    fn generators(&self, row: usize, _local_constants: &[F]) -> Vec<WitnessGeneratorRef<F, D>> {
+        let gen = SimpleExpVarGenerator {
+            row,
+            gate: self.clone(),
+            _phatom: PhantomData
+        };
+
+        vec![WitnessGeneratorRef::new(gen.adapter())]
+    }
+
+    // 1 for `base`, `num_bits` for bits, 1 for `output`, `num_bits` for accumulators
+    fn num_wires(&self) -> usize {
+        2 * self.num_bits + 2
+    }
+
+    fn num_constants(&self) -> usize {
+        0
+    }
+
+    // `acc_{j-1}^2 * bit * base`
+    fn degree(&self) -> usize {
+        4
+    }
+
+    fn num_constraints(&self) -> usize {
+        2 * self.num_bits + 1
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> PackedEvaluableBase<F, D> for SimpleExpVarGate {
+    fn eval_unfiltered_base_packed<P: PackedField<Scalar = F>>(
+        &self,
+        vars: EvaluationVarsBasePacked<P>,
+        mut yield_constr: StridedConstraintConsumer<P>,
+    ) {
+        let one: P = F::ONE.into();
+        let v_base = vars.local_wires[self.wire_base()];
+
+        let mut pre_acc = one;
+        for j in 0..self.num_bits {
+            let bit = vars.local_wires[self.wire_exponent_bit(self.num_bits - 1 - j)];
+            yield_constr.one(bit * (bit - one));
+
+            let multiplier = bit * v_base + one - bit;
+            let acc = vars.local_wires[self.wire_intermediate_value(j)];
+            yield_constr.one(acc - pre_acc * pre_acc * multiplier);
+
+            pre_acc = acc;
+        }
+
+        let output = vars.local_wires[self.wire_output()];
+        yield_constr.one(output - pre_acc);
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SimpleExpVarGenerator<F: RichField + Extendable<D>, const D: usize> {
+    pub row: usize,
+    pub gate: SimpleExpVarGate,
+    pub _phatom: PhantomData<F>
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> SimpleGenerator<F, D>
+    for SimpleExpVarGenerator<F, D> {
+
+    fn id(&self) -> String {
+        format!("{self:?}")
+    }
+
+    fn dependencies(&self) -> Vec<Target> {
+        let local_target = |column: usize| -> Target {
+            Target::wire(self.row, column)
+        };
+
+        let mut deps = Vec::with_capacity(self.gate.num_bits + 1);
+        deps.push(local_target(self.gate.wire_base()));
+
+        for i in 0..self.gate.num_bits {
+            deps.push(local_target(self.gate.wire_exponent_bit(i)));
+        }
+
+        deps
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let get_wire_target = |column: usize| -> Target {
+            Target::wire(self.row, column)
+        };
+
+        let get_wire_value = |wire: usize| -> F { witness.get_target(Target::wire(self.row, wire)) };
+
+        let base = get_wire_value(self.gate.wire_base());
+
+        let mut acc = F::ONE;
+        for j in 0..self.gate.num_bits {
+            let bit = get_wire_value(self.gate.wire_exponent_bit(self.gate.num_bits - 1 - j));
+
+            // Same formula as the constraint, so that a non-boolean bit is only caught by the booleanity check
+            acc = acc.square() * (bit * base + F::ONE - bit);
+
+            out_buffer.set_target(get_wire_target(self.gate.wire_intermediate_value(j)), acc);
+        }
+
+        out_buffer.set_target(get_wire_target(self.gate.wire_output()), acc)
+    }
+
+    fn serialize(&self, dst: &mut Vec<u8>, _common_data: &CommonCircuitData<F, D>) -> IoResult<()> {
+        dst.write_usize(self.row)?;
+        self.gate.serialize(dst, _common_data)
+    }
+
+    fn deserialize(src: &mut Buffer, _common_data: &CommonCircuitData<F, D>) -> IoResult<Self> {
+        let row = src.read_usize()?;
+        let gate = SimpleExpVarGate::deserialize(src, _common_data)?;
+        Ok(Self {row, gate, _phatom: PhantomData})
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use anyhow::Result;
+
+    use plonky2::gates::gate_testing::{test_eval_fns, test_low_degree};
+    use plonky2::field::goldilocks_field::GoldilocksField;
+    use plonky2::field::types::{Field, PrimeField64, Sample};
+    use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use plonky2::iop::witness::PartialWitness;
+
+    use crate::analysis::degree::measure_degree;
+    use crate::builder::CookBuilderExt;
+
+    #[test]
+    fn low_degree() {
+        let gate = SimpleExpVarGate::new(32, &CircuitConfig::standard_recursion_config());
+        test_low_degree::<GoldilocksField, _, 4>(gate);
+    }
+
+    #[test]
+    fn measured_degree() {
+        let config = CircuitConfig::standard_recursion_config();
+        let gate = SimpleExpVarGate::new(32, &config);
+        let report = measure_degree::<GoldilocksField, _, 4>(&gate, &config);
+        assert!(report.is_ok(), "{report}");
+    }
+
+    #[test]
+    fn eval_fns() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        let gate = SimpleExpVarGate::new(32, &CircuitConfig::standard_recursion_config());
+        test_eval_fns::<F, C, _, D>(gate)
+    }
+
+    #[test]
+    fn test_exp_var() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let base = builder.add_virtual_target();
+        let exponent = builder.add_virtual_target();
+        let exponent_bits = builder.split_le(exponent, 64);
+        let output = builder.exp_var(base, &exponent_bits);
+        builder.register_public_input(output);
+
+        let data = builder.build::<C>();
+
+        for _ in 0..3 {
+            let base_value = F::rand();
+            let exponent_value = F::rand();
+
+            let mut pw = PartialWitness::new();
+            pw.set_target(base, base_value);
+            pw.set_target(exponent, exponent_value);
+
+            let proof = data.prove(pw)?;
+            assert_eq!(
+                proof.public_inputs[0],
+                base_value.exp_u64(exponent_value.to_canonical_u64())
+            );
+
+            data.verify(proof)?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_non_boolean_bit() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config.clone());
+
+        let gate = SimpleExpVarGate::new(4, &config);
+        let row = builder.add_gate(gate.clone(), vec![]);
+
+        let data = builder.build::<C>();
+
+        // A bit of `2` with consistent accumulators must still be rejected by the booleanity constraint.
+        let base = F::from_canonical_u64(3);
+        let mut pw = PartialWitness::new();
+        pw.set_target(Target::wire(row, gate.wire_base()), base);
+        let mut acc = F::ONE;
+        for j in 0..gate.num_bits {
+            let i = gate.num_bits - 1 - j;
+            let bit = if i == 0 { F::TWO } else { F::ZERO };
+            pw.set_target(Target::wire(row, gate.wire_exponent_bit(i)), bit);
+            acc = acc.square() * (bit * base + F::ONE - bit);
+            pw.set_target(Target::wire(row, gate.wire_intermediate_value(j)), acc);
+        }
+        pw.set_target(Target::wire(row, gate.wire_output()), acc);
+
+        let proof = data.prove(pw).unwrap();
+        data.verify(proof).unwrap();
+    }
+}
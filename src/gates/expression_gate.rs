@@ -0,0 +1,791 @@
+#![allow(incomplete_features)]
+
+use core::marker::PhantomData;
+use core::ops::{Add, Mul, Sub};
+
+use plonky2::field::extension::Extendable;
+use plonky2::field::packed::PackedField;
+use plonky2::field::types::Field;
+use plonky2::gates::gate::Gate;
+use plonky2::gates::packed_util::PackedEvaluableBase;
+use plonky2::gates::util::StridedConstraintConsumer;
+use plonky2::hash::hash_types::RichField;
+use plonky2::iop::ext_target::ExtensionTarget;
+use plonky2::iop::generator::{GeneratedValues, SimpleGenerator, WitnessGeneratorRef};
+use plonky2::iop::target::Target;
+use plonky2::iop::witness::{PartitionWitness, Witness, WitnessWrite};
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+use plonky2::plonk::circuit_data::{CircuitConfig, CommonCircuitData};
+use plonky2::plonk::vars::{
+    EvaluationTargets, EvaluationVars, EvaluationVarsBase, EvaluationVarsBaseBatch,
+    EvaluationVarsBasePacked,
+};
+use plonky2::util::serialization::{Buffer, IoError, IoResult, Read, Write};
+
+/// A polynomial expression over named wires and constants, e.g.
+/// `Expr::wire("x") * Expr::wire("y") + Expr::constant("c")`.
+#[derive(Debug, Clone)]
+pub enum Expr {
+    Wire(String),
+    Constant(String),
+    Scalar(u64),
+    Add(Box<Expr>, Box<Expr>),
+    Sub(Box<Expr>, Box<Expr>),
+    Mul(Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    pub fn wire(name: &str) -> Self {
+        Self::Wire(name.to_string())
+    }
+
+    pub fn constant(name: &str) -> Self {
+        Self::Constant(name.to_string())
+    }
+
+    /// `value` is reduced modulo the order of the field the gate is evaluated over,
+    /// e.g. `Expr::scalar(u64::MAX)` is `2^32 - 2` in Goldilocks.
+    pub fn scalar(value: u64) -> Self {
+        Self::Scalar(value)
+    }
+}
+
+impl Add for Expr {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::Add(Box::new(self), Box::new(rhs))
+    }
+}
+
+impl Sub for Expr {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self::Sub(Box::new(self), Box::new(rhs))
+    }
+}
+
+impl Mul for Expr {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Self::Mul(Box::new(self), Box::new(rhs))
+    }
+}
+
+/// One step of a compiled `Expr`, evaluated on a stack in postfix order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExprOp {
+    Input(usize),
+    Constant(usize),
+    Scalar(u64),
+    Add,
+    Sub,
+    Mul,
+}
+
+/// The arithmetic an `Expression` is evaluated with.
+/// There is one implementation per evaluation path of a gate: native, packed, recursive and the generator.
+pub trait ExpressionEvaluator {
+    type Value: Copy;
+
+    fn input(&mut self, i: usize) -> Self::Value;
+    fn constant(&mut self, i: usize) -> Self::Value;
+    fn scalar(&mut self, value: u64) -> Self::Value;
+    fn add(&mut self, a: Self::Value, b: Self::Value) -> Self::Value;
+    fn sub(&mut self, a: Self::Value, b: Self::Value) -> Self::Value;
+    fn mul(&mut self, a: Self::Value, b: Self::Value) -> Self::Value;
+}
+
+/// Evaluates an `Expression` over any field-like type: `F`, `F::Extension` or a packed field.
+pub struct FieldEvaluator<'a, F: Field, T> {
+    pub inputs: &'a [T],
+    pub constants: &'a [T],
+    _phantom: PhantomData<F>,
+}
+
+impl<'a, F: Field, T> FieldEvaluator<'a, F, T> {
+    pub fn new(inputs: &'a [T], constants: &'a [T]) -> Self {
+        Self {
+            inputs,
+            constants,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<'a, F, T> ExpressionEvaluator for FieldEvaluator<'a, F, T>
+where
+    F: Field,
+    T: Copy + From<F> + Add<Output = T> + Sub<Output = T> + Mul<Output = T>,
+{
+    type Value = T;
+
+    fn input(&mut self, i: usize) -> T {
+        self.inputs[i]
+    }
+
+    fn constant(&mut self, i: usize) -> T {
+        self.constants[i]
+    }
+
+    fn scalar(&mut self, value: u64) -> T {
+        F::from_noncanonical_u64(value).into()
+    }
+
+    fn add(&mut self, a: T, b: T) -> T {
+        a + b
+    }
+
+    fn sub(&mut self, a: T, b: T) -> T {
+        a - b
+    }
+
+    fn mul(&mut self, a: T, b: T) -> T {
+        a * b
+    }
+}
+
+/// Evaluates an `Expression` in a recursive circuit.
+pub struct CircuitEvaluator<'a, F: RichField + Extendable<D>, const D: usize> {
+    pub builder: &'a mut CircuitBuilder<F, D>,
+    pub inputs: &'a [ExtensionTarget<D>],
+    pub constants: &'a [ExtensionTarget<D>],
+}
+
+impl<'a, F: RichField + Extendable<D>, const D: usize> ExpressionEvaluator for CircuitEvaluator<'a, F, D> {
+    type Value = ExtensionTarget<D>;
+
+    fn input(&mut self, i: usize) -> ExtensionTarget<D> {
+        self.inputs[i]
+    }
+
+    fn constant(&mut self, i: usize) -> ExtensionTarget<D> {
+        self.constants[i]
+    }
+
+    fn scalar(&mut self, value: u64) -> ExtensionTarget<D> {
+        self.builder.constant_extension(F::Extension::from_noncanonical_u64(value))
+    }
+
+    fn add(&mut self, a: ExtensionTarget<D>, b: ExtensionTarget<D>) -> ExtensionTarget<D> {
+        self.builder.add_extension(a, b)
+    }
+
+    fn sub(&mut self, a: ExtensionTarget<D>, b: ExtensionTarget<D>) -> ExtensionTarget<D> {
+        self.builder.sub_extension(a, b)
+    }
+
+    fn mul(&mut self, a: ExtensionTarget<D>, b: ExtensionTarget<D>) -> ExtensionTarget<D> {
+        self.builder.mul_extension(a, b)
+    }
+}
+
+/// The description of one operation: `output = expr(inputs, constants)`.
+/// The names are resolved once here, `expr` is kept as a postfix program over input and constant indices.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expression {
+    pub inputs: Vec<String>,
+    pub output: String,
+    pub constants: Vec<String>,
+    pub program: Vec<ExprOp>,
+}
+
+impl Expression {
+    pub fn new(inputs: &[&str], output: &str, constants: &[&str], expr: Expr) -> Self {
+        assert!(!inputs.contains(&output), "the output `{output}` is also an input");
+
+        let mut expression = Self {
+            inputs: inputs.iter().map(|s| s.to_string()).collect(),
+            output: output.to_string(),
+            constants: constants.iter().map(|s| s.to_string()).collect(),
+            program: Vec::new(),
+        };
+
+        let mut program = Vec::new();
+        expression.compile(&expr, &mut program);
+        expression.program = program;
+
+        expression
+    }
+
+    fn compile(&self, expr: &Expr, program: &mut Vec<ExprOp>) {
+        match expr {
+            Expr::Wire(name) => {
+                let i = self
+                    .inputs
+                    .iter()
+                    .position(|s| s == name)
+                    .unwrap_or_else(|| panic!("unknown input wire `{name}`"));
+                program.push(ExprOp::Input(i));
+            }
+            Expr::Constant(name) => {
+                let i = self
+                    .constants
+                    .iter()
+                    .position(|s| s == name)
+                    .unwrap_or_else(|| panic!("unknown constant `{name}`"));
+                program.push(ExprOp::Constant(i));
+            }
+            Expr::Scalar(value) => program.push(ExprOp::Scalar(*value)),
+            Expr::Add(a, b) => {
+                self.compile(a, program);
+                self.compile(b, program);
+                program.push(ExprOp::Add);
+            }
+            Expr::Sub(a, b) => {
+                self.compile(a, program);
+                self.compile(b, program);
+                program.push(ExprOp::Sub);
+            }
+            Expr::Mul(a, b) => {
+                self.compile(a, program);
+                self.compile(b, program);
+                program.push(ExprOp::Mul);
+            }
+        }
+    }
+
+    pub fn eval<E: ExpressionEvaluator>(&self, evaluator: &mut E) -> E::Value {
+        let mut stack = Vec::with_capacity(self.program.len());
+        for op in self.program.iter() {
+            let value = match *op {
+                ExprOp::Input(i) => evaluator.input(i),
+                ExprOp::Constant(i) => evaluator.constant(i),
+                ExprOp::Scalar(value) => evaluator.scalar(value),
+                ExprOp::Add | ExprOp::Sub | ExprOp::Mul => {
+                    let b = stack.pop().expect("malformed expression");
+                    let a = stack.pop().expect("malformed expression");
+                    match *op {
+                        ExprOp::Add => evaluator.add(a, b),
+                        ExprOp::Sub => evaluator.sub(a, b),
+                        _ => evaluator.mul(a, b),
+                    }
+                }
+            };
+            stack.push(value);
+        }
+
+        debug_assert_eq!(stack.len(), 1);
+        stack.pop().expect("empty expression")
+    }
+
+    /// The degree of `expr` as a polynomial in the wires and constants.
+    pub fn degree(&self) -> usize {
+        let mut stack = Vec::with_capacity(self.program.len());
+        for op in self.program.iter() {
+            let degree = match *op {
+                ExprOp::Input(_) | ExprOp::Constant(_) => 1,
+                ExprOp::Scalar(_) => 0,
+                ExprOp::Add | ExprOp::Sub | ExprOp::Mul => {
+                    let b: usize = stack.pop().expect("malformed expression");
+                    let a: usize = stack.pop().expect("malformed expression");
+                    if *op == ExprOp::Mul {
+                        a + b
+                    } else {
+                        a.max(b)
+                    }
+                }
+            };
+            stack.push(degree);
+        }
+
+        stack.pop().unwrap_or(0)
+    }
+
+    fn write_string(dst: &mut Vec<u8>, s: &str) -> IoResult<()> {
+        dst.write_usize(s.len())?;
+        dst.write_all(s.as_bytes())
+    }
+
+    /// Reads a count of items taking at least one byte each, so a corrupt count fails before anything is allocated.
+    fn read_len(src: &mut Buffer) -> IoResult<usize> {
+        let len = src.read_usize()?;
+        if len > src.remaining() {
+            return Err(IoError);
+        }
+        Ok(len)
+    }
+
+    fn read_string(src: &mut Buffer) -> IoResult<String> {
+        let len = Self::read_len(src)?;
+        let mut bytes = vec![0u8; len];
+        src.read_exact(&mut bytes)?;
+        String::from_utf8(bytes).map_err(|_| IoError)
+    }
+
+    pub fn serialize(&self, dst: &mut Vec<u8>) -> IoResult<()> {
+        dst.write_usize(self.inputs.len())?;
+        for s in self.inputs.iter() {
+            Self::write_string(dst, s)?;
+        }
+        Self::write_string(dst, &self.output)?;
+        dst.write_usize(self.constants.len())?;
+        for s in self.constants.iter() {
+            Self::write_string(dst, s)?;
+        }
+
+        dst.write_usize(self.program.len())?;
+        for op in self.program.iter() {
+            match *op {
+                ExprOp::Input(i) => {
+                    dst.write_u8(0)?;
+                    dst.write_usize(i)?;
+                }
+                ExprOp::Constant(i) => {
+                    dst.write_u8(1)?;
+                    dst.write_usize(i)?;
+                }
+                ExprOp::Scalar(value) => {
+                    dst.write_u8(2)?;
+                    dst.write_usize(value as usize)?;
+                }
+                ExprOp::Add => dst.write_u8(3)?,
+                ExprOp::Sub => dst.write_u8(4)?,
+                ExprOp::Mul => dst.write_u8(5)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn deserialize(src: &mut Buffer) -> IoResult<Self> {
+        let num_inputs = Self::read_len(src)?;
+        let inputs = (0..num_inputs)
+            .map(|_| Self::read_string(src))
+            .collect::<IoResult<Vec<_>>>()?;
+        let output = Self::read_string(src)?;
+        let num_constants = Self::read_len(src)?;
+        let constants = (0..num_constants)
+            .map(|_| Self::read_string(src))
+            .collect::<IoResult<Vec<_>>>()?;
+
+        let program_len = Self::read_len(src)?;
+        let mut program = Vec::with_capacity(program_len);
+        for _ in 0..program_len {
+            let op = match src.read_u8()? {
+                0 => ExprOp::Input(src.read_usize()?),
+                1 => ExprOp::Constant(src.read_usize()?),
+                2 => ExprOp::Scalar(src.read_usize()? as u64),
+                3 => ExprOp::Add,
+                4 => ExprOp::Sub,
+                5 => ExprOp::Mul,
+                _ => return Err(IoError),
+            };
+            program.push(op);
+        }
+
+        let expression = Self {
+            inputs,
+            output,
+            constants,
+            program,
+        };
+        expression.check_program()?;
+        Ok(expression)
+    }
+
+    /// `eval` only indexes the inputs and constants of the expression, and ends with exactly one value on the stack.
+    fn check_program(&self) -> IoResult<()> {
+        let mut depth = 0usize;
+        for op in self.program.iter() {
+            depth = match *op {
+                ExprOp::Input(i) if i < self.inputs.len() => depth + 1,
+                ExprOp::Constant(i) if i < self.constants.len() => depth + 1,
+                ExprOp::Scalar(_) => depth + 1,
+                ExprOp::Add | ExprOp::Sub | ExprOp::Mul if depth >= 2 => depth - 1,
+                _ => return Err(IoError),
+            };
+        }
+
+        if depth == 1 {
+            Ok(())
+        } else {
+            Err(IoError)
+        }
+    }
+}
+
+/// A gate derived from a single `Expression`.
+/// As many ops as fit in the routed wires are placed in one row, every op uses its inputs followed by its output,
+/// and the constants are shared by all the ops of a row.
+#[derive(Debug, Clone)]
+pub struct ExpressionGate {
+    pub expression: Expression,
+    pub num_ops: usize,
+}
+
+impl ExpressionGate {
+    pub fn new_from_config(expression: Expression, config: &CircuitConfig) -> Self {
+        let num_ops = config.num_routed_wires / (expression.inputs.len() + 1);
+        assert!(num_ops > 0, "too many inputs for one row");
+
+        Self {
+            expression,
+            num_ops,
+        }
+    }
+
+    pub fn wires_per_op(&self) -> usize {
+        self.expression.inputs.len() + 1
+    }
+
+    pub fn wire_ith_input(&self, i: usize, j: usize) -> usize {
+        debug_assert!(j < self.expression.inputs.len());
+        self.wires_per_op() * i + j
+    }
+
+    pub fn wire_ith_output(&self, i: usize) -> usize {
+        self.wires_per_op() * i + self.expression.inputs.len()
+    }
+
+    /// The wire of the input or output called `name` in the `i`-th op.
+    pub fn wire_ith_named(&self, i: usize, name: &str) -> usize {
+        if name == self.expression.output {
+            return self.wire_ith_output(i);
+        }
+        let j = self
+            .expression
+            .inputs
+            .iter()
+            .position(|s| s == name)
+            .unwrap_or_else(|| panic!("unknown wire `{name}`"));
+        self.wire_ith_input(i, j)
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> Gate<F, D> for ExpressionGate {
+    fn id(&self) -> String {
+        format!("{self:?}")
+    }
+
+    fn serialize(&self, dst: &mut Vec<u8>, _common_data: &CommonCircuitData<F, D>) -> IoResult<()> {
+        self.expression.serialize(dst)?;
+        dst.write_usize(self.num_ops)
+    }
+
+    fn deserialize(src: &mut Buffer, _common_data: &CommonCircuitData<F, D>) -> IoResult<Self> {
+        let expression = Expression::deserialize(src)?;
+        let num_ops = src.read_usize()?;
+        Ok(Self { expression, num_ops })
+    }
+
+    fn eval_unfiltered(&self, vars: EvaluationVars<F, D>) -> Vec<F::Extension> {
+        let mut constraints = Vec::with_capacity(self.num_ops);
+        for i in 0..self.num_ops {
+            let inputs: Vec<F::Extension> = (0..self.expression.inputs.len())
+                .map(|j| vars.local_wires[self.wire_ith_input(i, j)])
+                .collect();
+            let output = vars.local_wires[self.wire_ith_output(i)];
+
+            let computed_output = self
+                .expression
+                .eval(&mut FieldEvaluator::<F, _>::new(&inputs, vars.local_constants));
+
+            constraints.push(output - computed_output);
+        }
+
+        constraints
+    }
+
+    fn eval_unfiltered_base_one(
+        &self,
+        _vars: EvaluationVarsBase<F>,
+        _yield_constr: StridedConstraintConsumer<F>,
+    ) {
+        panic!("use eval_unfiltered_base_packed instead");
+    }
+
+    fn eval_unfiltered_base_batch(&self, vars_base: EvaluationVarsBaseBatch<F>) -> Vec<F> {
+        self.eval_unfiltered_base_batch_packed(vars_base)
+    }
+
+    fn eval_unfiltered_circuit(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: EvaluationTargets<D>,
+    ) -> Vec<ExtensionTarget<D>> {
+        let mut constraints = Vec::with_capacity(self.num_ops);
+        for i in 0..self.num_ops {
+            let inputs: Vec<ExtensionTarget<D>> = (0..self.expression.inputs.len())
+                .map(|j| vars.local_wires[self.wire_ith_input(i, j)])
+                .collect();
+            let output = vars.local_wires[self.wire_ith_output(i)];
+
+            let computed_output = self.expression.eval(&mut CircuitEvaluator {
+                builder: &mut *builder,
+                inputs: &inputs,
+                constants: vars.local_constants,
+            });
+
+            constraints.push(builder.sub_extension(output, computed_output));
+        }
+
+        constraints
+    }
+
+    fn generators(&self, row: usize, local_constants: &[F]) -> Vec<WitnessGeneratorRef<F, D>> {
+        (0..self.num_ops)
+            .map(|i| {
+                WitnessGeneratorRef::new(
+                    ExpressionGenerator {
+                        row,
+                        i,
+                        gate: self.clone(),
+                        constants: local_constants[..self.expression.constants.len()].to_vec(),
+                    }
+                    .adapter(),
+                )
+            })
+            .collect()
+    }
+
+    fn num_wires(&self) -> usize {
+        self.num_ops * self.wires_per_op()
+    }
+
+    fn num_constants(&self) -> usize {
+        self.expression.constants.len()
+    }
+
+    // `output - expr` is at least linear because of the output wire
+    fn degree(&self) -> usize {
+        self.expression.degree().max(1)
+    }
+
+    fn num_constraints(&self) -> usize {
+        self.num_ops
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> PackedEvaluableBase<F, D> for ExpressionGate {
+    fn eval_unfiltered_base_packed<P: PackedField<Scalar = F>>(
+        &self,
+        vars: EvaluationVarsBasePacked<P>,
+        mut yield_constr: StridedConstraintConsumer<P>,
+    ) {
+        let constants: Vec<P> = (0..self.expression.constants.len())
+            .map(|k| vars.local_constants[k])
+            .collect();
+
+        for i in 0..self.num_ops {
+            let inputs: Vec<P> = (0..self.expression.inputs.len())
+                .map(|j| vars.local_wires[self.wire_ith_input(i, j)])
+                .collect();
+            let output = vars.local_wires[self.wire_ith_output(i)];
+
+            let computed_output = self
+                .expression
+                .eval(&mut FieldEvaluator::<F, _>::new(&inputs, &constants));
+
+            yield_constr.one(output - computed_output);
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ExpressionGenerator<F: RichField + Extendable<D>, const D: usize> {
+    pub row: usize,
+    pub i: usize,
+    pub gate: ExpressionGate,
+    pub constants: Vec<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> SimpleGenerator<F, D>
+    for ExpressionGenerator<F, D> {
+
+    fn id(&self) -> String {
+        format!("{self:?}")
+    }
+
+    fn dependencies(&self) -> Vec<Target> {
+        (0..self.gate.expression.inputs.len())
+            .map(|j| Target::wire(self.row, self.gate.wire_ith_input(self.i, j)))
+            .collect()
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let inputs: Vec<F> = (0..self.gate.expression.inputs.len())
+            .map(|j| witness.get_target(Target::wire(self.row, self.gate.wire_ith_input(self.i, j))))
+            .collect();
+
+        let computed_output = self
+            .gate
+            .expression
+            .eval(&mut FieldEvaluator::<F, F>::new(&inputs, &self.constants));
+
+        out_buffer.set_target(Target::wire(self.row, self.gate.wire_ith_output(self.i)), computed_output)
+    }
+
+    fn serialize(&self, dst: &mut Vec<u8>, _common_data: &CommonCircuitData<F, D>) -> IoResult<()> {
+        dst.write_usize(self.row)?;
+        dst.write_usize(self.i)?;
+        self.gate.serialize(dst, _common_data)?;
+        dst.write_field_vec(&self.constants)
+    }
+
+    fn deserialize(src: &mut Buffer, _common_data: &CommonCircuitData<F, D>) -> IoResult<Self> {
+        let row = src.read_usize()?;
+        let i = src.read_usize()?;
+        let gate = ExpressionGate::deserialize(src, _common_data)?;
+        let constants = src.read_field_vec(gate.expression.constants.len())?;
+        Ok(Self { row, i, gate, constants })
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use anyhow::Result;
+
+    use plonky2::field::types::Sample;
+    use plonky2::gates::gate_testing::{test_eval_fns, test_low_degree};
+    use plonky2::field::goldilocks_field::GoldilocksField;
+    use plonky2::iop::witness::PartialWitness;
+    use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+
+    use crate::analysis::degree::measure_degree;
+    use crate::builder::CookBuilderExt;
+
+    // `c * x^2 + y - 3`
+    fn test_expression() -> Expression {
+        Expression::new(
+            &["x", "y"],
+            "out",
+            &["c"],
+            Expr::constant("c") * Expr::wire("x") * Expr::wire("x") + Expr::wire("y") - Expr::scalar(3),
+        )
+    }
+
+    #[test]
+    fn degree_and_layout() {
+        let gate = ExpressionGate::new_from_config(test_expression(), &CircuitConfig::standard_recursion_config());
+        assert_eq!(gate.wires_per_op(), 3);
+        assert_eq!(gate.wire_ith_named(1, "y"), gate.wire_ith_input(1, 1));
+        assert_eq!(gate.wire_ith_named(1, "out"), gate.wire_ith_output(1));
+        assert_eq!(<ExpressionGate as Gate<GoldilocksField, 2>>::degree(&gate), 3);
+        assert_eq!(<ExpressionGate as Gate<GoldilocksField, 2>>::num_constants(&gate), 1);
+    }
+
+    #[test]
+    fn large_scalar() {
+        let expression = Expression::new(&["x"], "out", &[], Expr::wire("x") + Expr::scalar(u64::MAX));
+        let x = [GoldilocksField::ONE];
+        let value = expression.eval(&mut FieldEvaluator::<GoldilocksField, GoldilocksField>::new(&x, &[]));
+        assert_eq!(value, GoldilocksField::from_canonical_u64((1 << 32) - 1));
+    }
+
+    #[test]
+    fn low_degree() {
+        let gate = ExpressionGate::new_from_config(test_expression(), &CircuitConfig::standard_recursion_config());
+        test_low_degree::<GoldilocksField, _, 4>(gate);
+    }
+
+    #[test]
+    fn measured_degree() {
+        let config = CircuitConfig::standard_recursion_config();
+        let gate = ExpressionGate::new_from_config(test_expression(), &config);
+        let report = measure_degree::<GoldilocksField, _, 4>(&gate, &config);
+        assert!(report.is_ok(), "{report}");
+    }
+
+    #[test]
+    fn eval_fns() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        let gate = ExpressionGate::new_from_config(test_expression(), &CircuitConfig::standard_recursion_config());
+        test_eval_fns::<F, C, _, D>(gate)
+    }
+
+    #[test]
+    fn test_expression_op() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        // The same operation as `SimpleMulAddTestGate`
+        let mul_add = Expression::new(
+            &["x1", "x2", "x3", "x4", "x5"],
+            "out",
+            &[],
+            Expr::wire("x1") * Expr::wire("x2") + Expr::wire("x3") + Expr::wire("x4") + Expr::wire("x5"),
+        );
+        let c = F::rand();
+
+        let inputs: Vec<Target> = (0..5).map(|_| builder.add_virtual_target()).collect();
+        let out_0 = builder.expression_op(&mul_add, &inputs, &[]);
+        let out_1 = builder.expression_op(&test_expression(), &inputs[..2], &[c]);
+        builder.register_public_input(out_0);
+        builder.register_public_input(out_1);
+
+        let data = builder.build::<C>();
+
+        let values = F::rand_vec(5);
+        let mut pw = PartialWitness::new();
+        for (&t, &v) in inputs.iter().zip(values.iter()) {
+            pw.set_target(t, v);
+        }
+
+        let proof = data.prove(pw)?;
+        assert_eq!(proof.public_inputs[0], values[0] * values[1] + values[2] + values[3] + values[4]);
+        assert_eq!(proof.public_inputs[1], c * values[0].square() + values[1] - F::from_canonical_u64(3));
+
+        data.verify(proof)
+    }
+
+    #[test]
+    fn serialize_expression() -> Result<()> {
+        let expression = test_expression();
+
+        let mut bytes = Vec::new();
+        expression.serialize(&mut bytes).map_err(|_| anyhow::Error::msg("Expression serialization failed."))?;
+        let mut buffer = Buffer::new(&bytes);
+        let read = Expression::deserialize(&mut buffer).map_err(|_| anyhow::Error::msg("Expression deserialization failed."))?;
+
+        assert_eq!(read, expression);
+        Ok(())
+    }
+
+    #[test]
+    fn corrupt_expression() -> Result<()> {
+        let read = |expression: &Expression| {
+            let mut bytes = Vec::new();
+            expression.serialize(&mut bytes).unwrap();
+            Expression::deserialize(&mut Buffer::new(&bytes))
+        };
+
+        let mut expression = test_expression();
+        expression.program[0] = ExprOp::Input(2);
+        assert!(read(&expression).is_err());
+
+        let mut expression = test_expression();
+        expression.program[0] = ExprOp::Constant(1);
+        assert!(read(&expression).is_err());
+
+        let mut expression = test_expression();
+        expression.program.push(ExprOp::Add);
+        assert!(read(&expression).is_err());
+
+        let mut expression = test_expression();
+        expression.program.push(ExprOp::Scalar(1));
+        assert!(read(&expression).is_err());
+
+        let mut expression = test_expression();
+        expression.program.clear();
+        assert!(read(&expression).is_err());
+
+        // A length read from the file is never allocated before it is checked.
+        let mut bytes = Vec::new();
+        bytes.write_usize(1).unwrap();
+        bytes.write_usize(usize::MAX).unwrap();
+        assert!(Expression::deserialize(&mut Buffer::new(&bytes)).is_err());
+        Ok(())
+    }
+}
@@ -12,7 +12,7 @@ use plonky2::hash::hash_types::RichField;
 use plonky2::iop::ext_target::ExtensionTarget;
 use plonky2::iop::generator::{GeneratedValues, SimpleGenerator, WitnessGeneratorRef};
 use plonky2::iop::target::Target;
-use plonky2::iop::witness::{PartitionWitness, Witness, WitnessWrite};
+use plonky2::iop::witness::{PartitionWitness, WitnessWrite};
 use plonky2::plonk::circuit_builder::CircuitBuilder;
 use plonky2::plonk::circuit_data::{CircuitConfig, CommonCircuitData};
 use plonky2::plonk::vars::{
@@ -21,6 +21,8 @@ use plonky2::plonk::vars::{
 };
 use plonky2::util::serialization::{Buffer, IoResult, Read, Write};
 
+use crate::gates::layout::{Constant, GateLayout, Wire, Wires};
+
 /// *** Note: Remember that, `gate` is used for proving not calculating!!! ***
 /// This example is trivial but shows how to design and use `gate`
 #[derive(Debug, Clone, Default)]
@@ -38,7 +40,7 @@ impl SimpleExpConstantGate {
     }
 
     /// Determine the maximum number of operations that can fit in one gate for the given config.
-    fn max_power(config: &CircuitConfig) -> usize {
+    pub fn max_power(config: &CircuitConfig) -> usize {
         // 3 wires are reserved for the 0 power, base and output.
         let max_for_routed_wires = config.num_routed_wires - 3;
         let max_for_wires = (config.num_wires - 3) / 2;
@@ -49,14 +51,24 @@ impl SimpleExpConstantGate {
     pub fn wire_power_i(&self, i: usize) -> usize {
         debug_assert!(i <= self.num_limbs);
         debug_assert!(i > 0);
-        i - 1
+        self.wire_ith_power(0, i - 1)
     }
 
     pub fn wire_output(&self) -> usize {
-        self.num_limbs
+        self.wire_ith_output(0)
     }
 }
 
+// `num_limbs` for powers, 1 for `output`, and the base is a constant
+#[derive(GateLayout)]
+#[gate_layout(gate = SimpleExpConstantGate)]
+pub struct SimpleExpConstantLayout {
+    #[gate_layout(len = num_limbs)]
+    pub power: Wires,
+    pub output: Wire,
+    pub base: Constant,
+}
+
 impl<F: RichField + Extendable<D>, const D: usize> Gate<F, D> for SimpleExpConstantGate {
     fn id(&self) -> String {
         format!("{self:?}")
@@ -76,7 +88,7 @@ impl<F: RichField + Extendable<D>, const D: usize> Gate<F, D> for SimpleExpConst
         let mut constraints = Vec::with_capacity(self.num_limbs + 1);
 
         let power_0_wire: <F as Extendable<D>>::Extension = F::ONE.into();
-        let v_base = vars.local_constants[0];
+        let v_base = vars.local_constants[Self::const_base()];
 
         let mut computed_cur_power = power_0_wire;
 
@@ -119,7 +131,7 @@ impl<F: RichField + Extendable<D>, const D: usize> Gate<F, D> for SimpleExpConst
 
         let mut constraints = Vec::with_capacity(self.num_limbs + 2);
 
-        let v_base = vars.local_constants[0];
+        let v_base = vars.local_constants[Self::const_base()];
         let mut computed_cur_power = builder.constant_extension(F::ONE.into());
         
         let mut pre_power = computed_cur_power;
@@ -144,7 +156,7 @@ impl<F: RichField + Extendable<D>, const D: usize> Gate<F, D> for SimpleExpConst
         let gen = SimpleExpConstantGenerator {
             row,
             gate: self.clone(),
-            const_base: local_constants[0]
+            const_base: local_constants[Self::const_base()]
         };
 
         vec![WitnessGeneratorRef::new(gen.adapter())]
@@ -152,11 +164,11 @@ impl<F: RichField + Extendable<D>, const D: usize> Gate<F, D> for SimpleExpConst
 
     // `num_limbs` for powers, 1 for `output`
     fn num_wires(&self) -> usize {
-        self.num_limbs + 1
+        SimpleExpConstantGate::num_wires(self)
     }
 
     fn num_constants(&self) -> usize {
-        1
+        SimpleExpConstantGate::layout_num_constants()
     }
 
     // how to determine the `degree`?
@@ -180,7 +192,7 @@ impl<F: RichField + Extendable<D>, const D: usize> PackedEvaluableBase<F, D> for
     ) {
 
         let mut computed_cur_power: P = F::ONE.into();
-        let v_base = vars.local_constants[0];
+        let v_base = vars.local_constants[Self::const_base()];
 
         let mut pre_wire_value = computed_cur_power;
         for i in 1..(self.num_limbs + 1) {
@@ -212,48 +224,30 @@ impl<F: RichField + Extendable<D>, const D: usize> SimpleGenerator<F, D>
         format!("{self:?}")
     }
 
+    // `const_base` is a constant of the gate and every power is written by this generator,
+    // so there is nothing to wait for
     fn dependencies(&self) -> Vec<Target> {
-        let local_target = |column: usize| -> Target {
-            Target::wire(self.row, column)
-        };
-
-        let mut deps = Vec::with_capacity(self.gate.num_limbs);
-
-        for i in 1..(self.gate.num_limbs + 1) {
-            deps.push(local_target(self.gate.wire_power_i(i)));
-        }
-
-        deps
+        vec![]
     }
 
     /// This method is used to set the targets from the witness,
     /// which will be used for proving
-    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+    fn run_once(&self, _witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
         let get_wire_target = |column: usize| -> Target { 
             Target::wire(self.row, column)
         };
 
-        let get_wire_value = |wire: usize| -> F { witness.get_target(Target::wire(self.row, wire)) };
-
         let base = self.const_base;
-        // let mut computed_output = self.const_0;
         let mut computed_output = F::ONE;
 
-        let mut pre_power_value = computed_output;
         for i in 1..(self.gate.num_limbs + 1) {
-            computed_output = pre_power_value * base;
+            computed_output *= base;
 
             out_buffer.set_target(get_wire_target(self.gate.wire_power_i(i)), computed_output);
-
-            pre_power_value = get_wire_value(self.gate.wire_power_i(i));
-
-            // println!("In `run_once`, row {} column {}, value {}", self.row, self.gate.wire_power_i(i), pre_power_value);
         }
 
         let output_target = get_wire_target(self.gate.wire_output());
 
-        // println!("In `run_once output`, row {} column {}, value {}", self.row, self.gate.wire_output(), computed_output);
-
         out_buffer.set_target(output_target, computed_output)
     }
 
@@ -286,6 +280,8 @@ mod tests {
     use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
     use plonky2::iop::witness::{PartialWitness, WitnessWrite};
 
+    use crate::analysis::degree::measure_degree;
+
     #[test]
     fn low_degree() {
         // Here we can set the input parameter `power` very large, for example `16000` just needs abount 2s.
@@ -294,6 +290,14 @@ mod tests {
         test_low_degree::<GoldilocksField, _, 4>(gate);
     }
 
+    #[test]
+    fn measured_degree() {
+        let config = CircuitConfig::standard_recursion_config();
+        let gate = SimpleExpConstantGate::new(16, &config);
+        let report = measure_degree::<GoldilocksField, _, 4>(&gate, &config);
+        assert!(report.is_ok(), "{report}");
+    }
+
     #[test]
     fn eval_fns() -> Result<()> {
         const D: usize = 2;
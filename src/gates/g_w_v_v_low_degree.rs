@@ -0,0 +1,310 @@
+#![allow(incomplete_features)]
+
+use core::marker::PhantomData;
+
+use plonky2::field::extension::Extendable;
+use plonky2::field::packed::PackedField;
+use plonky2::gates::gate::Gate;
+use plonky2::gates::packed_util::PackedEvaluableBase;
+use plonky2::gates::util::StridedConstraintConsumer;
+use plonky2::hash::hash_types::RichField;
+use plonky2::iop::ext_target::ExtensionTarget;
+use plonky2::iop::generator::{GeneratedValues, SimpleGenerator, WitnessGeneratorRef};
+use plonky2::iop::target::Target;
+use plonky2::iop::witness::{PartitionWitness, Witness, WitnessWrite};
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+use plonky2::plonk::circuit_data::{CircuitConfig, CommonCircuitData};
+use plonky2::plonk::vars::{
+    EvaluationTargets, EvaluationVars, EvaluationVarsBase, EvaluationVarsBaseBatch,
+    EvaluationVarsBasePacked,
+};
+use plonky2::util::serialization::{Buffer, IoResult, Read, Write};
+
+/// The low degree version of `SimpleExpTestGate`.
+/// Like `SimpleExpConstantGate`, every power is constrained by the previous power wire instead of being
+/// recomputed from `base`, so the degree is 2 whatever the power is.
+#[derive(Debug, Clone, Default)]
+pub struct SimpleExpLowDegreeGate {
+    pub num_limbs: usize
+}
+
+impl SimpleExpLowDegreeGate {
+    pub fn new(power: usize, config: &CircuitConfig) -> Self {
+        debug_assert!(power > 0);
+        debug_assert!(power <= Self::max_power(config));
+
+        Self {
+            num_limbs: power,
+        }
+    }
+
+    /// Determine the maximum power that can fit in one gate for the given config.
+    pub fn max_power(config: &CircuitConfig) -> usize {
+        // `base` and `output` are routed, the powers can use the advice wires as well.
+        config.num_wires - 2
+    }
+
+    pub fn wire_base(&self) -> usize {
+        0
+    }
+
+    pub fn wire_output(&self) -> usize {
+        1
+    }
+
+    /// The `i`th power of the exponent.
+    pub fn wire_power_i(&self, i: usize) -> usize {
+        debug_assert!(i <= self.num_limbs);
+        debug_assert!(i > 0);
+
+        i + 1
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> Gate<F, D> for SimpleExpLowDegreeGate {
+    fn id(&self) -> String {
+        format!("{self:?}")
+    }
+
+    fn serialize(&self, dst: &mut Vec<u8>, _common_data: &CommonCircuitData<F, D>) -> IoResult<()> {
+        dst.write_usize(self.num_limbs)
+    }
+
+    fn deserialize(src: &mut Buffer, _common_data: &CommonCircuitData<F, D>) -> IoResult<Self> {
+        let num_limbs = src.read_usize()?;
+        Ok(Self { num_limbs })
+    }
+
+    fn eval_unfiltered(&self, vars: EvaluationVars<F, D>) -> Vec<F::Extension> {
+        let mut constraints = Vec::with_capacity(self.num_limbs + 1);
+
+        let v_base = vars.local_wires[self.wire_base()];
+
+        let mut pre_power: F::Extension = F::ONE.into();
+        for i in 1..(self.num_limbs + 1) {
+            let power_i_wire = vars.local_wires[self.wire_power_i(i)];
+
+            constraints.push(power_i_wire - pre_power * v_base);
+
+            pre_power = power_i_wire;
+        }
+
+        let output = vars.local_wires[self.wire_output()];
+        constraints.push(pre_power - output);
+
+        constraints
+    }
+
+    fn eval_unfiltered_base_one(
+        &self,
+        _vars: EvaluationVarsBase<F>,
+        _yield_constr: StridedConstraintConsumer<F>,
+    ) {
+        panic!("use eval_unfiltered_base_packed instead");
+    }
+
+    fn eval_unfiltered_base_batch(&self, vars_base: EvaluationVarsBaseBatch<F>) -> Vec<F> {
+        self.eval_unfiltered_base_batch_packed(vars_base)
+    }
+
+    fn eval_unfiltered_circuit(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: EvaluationTargets<D>,
+    ) -> Vec<ExtensionTarget<D>> {
+        let mut constraints = Vec::with_capacity(self.num_limbs + 1);
+
+        let v_base = vars.local_wires[self.wire_base()];
+
+        let mut pre_power = builder.one_extension();
+        for i in 1..(self.num_limbs + 1) {
+            let power_i_wire = vars.local_wires[self.wire_power_i(i)];
+
+            // `power_i - pre_power * base` in a single arithmetic op
+            constraints.push(builder.arithmetic_extension(
+                F::NEG_ONE,
+                F::ONE,
+                pre_power,
+                v_base,
+                power_i_wire,
+            ));
+
+            pre_power = power_i_wire;
+        }
+
+        let output = vars.local_wires[self.wire_output()];
+        constraints.push(builder.sub_extension(pre_power, output));
+
+        constraints
+    }
+
+    fn generators(&self, row: usize, _local_constants: &[F]) -> Vec<WitnessGeneratorRef<F, D>> {
+        let gen = SimpleExpLowDegreeGenerator {
+            row,
+            gate: self.clone(),
+            _phatom: PhantomData
+        };
+
+        vec![WitnessGeneratorRef::new(gen.adapter())]
+    }
+
+    // 1 for `base`, 1 for `output`, `num_limbs` for powers
+    fn num_wires(&self) -> usize {
+        self.num_limbs + 2
+    }
+
+    fn num_constants(&self) -> usize {
+        0
+    }
+
+    // Each constraint only multiplies two wires: the previous power and `base`
+    fn degree(&self) -> usize {
+        2
+    }
+
+    fn num_constraints(&self) -> usize {
+        self.num_limbs + 1
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> PackedEvaluableBase<F, D> for SimpleExpLowDegreeGate {
+    fn eval_unfiltered_base_packed<P: PackedField<Scalar = F>>(
+        &self,
+        vars: EvaluationVarsBasePacked<P>,
+        mut yield_constr: StridedConstraintConsumer<P>,
+    ) {
+        let v_base = vars.local_wires[self.wire_base()];
+
+        let mut pre_power: P = F::ONE.into();
+        for i in 1..(self.num_limbs + 1) {
+            let power_i_wire = vars.local_wires[self.wire_power_i(i)];
+
+            yield_constr.one(power_i_wire - pre_power * v_base);
+
+            pre_power = power_i_wire;
+        }
+
+        let output = vars.local_wires[self.wire_output()];
+        yield_constr.one(pre_power - output);
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SimpleExpLowDegreeGenerator<F: RichField + Extendable<D>, const D: usize> {
+    pub row: usize,
+    pub gate: SimpleExpLowDegreeGate,
+    pub _phatom: PhantomData<F>
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> SimpleGenerator<F, D>
+    for SimpleExpLowDegreeGenerator<F, D> {
+
+    fn id(&self) -> String {
+        format!("{self:?}")
+    }
+
+    // Only `base` is an input, all the powers are written by this generator
+    fn dependencies(&self) -> Vec<Target> {
+        vec![Target::wire(self.row, self.gate.wire_base())]
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let get_wire_target = |column: usize| -> Target {
+            Target::wire(self.row, column)
+        };
+
+        let base = witness.get_target(get_wire_target(self.gate.wire_base()));
+
+        let mut computed_output = F::ONE;
+        for i in 1..(self.gate.num_limbs + 1) {
+            computed_output *= base;
+            out_buffer.set_target(get_wire_target(self.gate.wire_power_i(i)), computed_output);
+        }
+
+        out_buffer.set_target(get_wire_target(self.gate.wire_output()), computed_output)
+    }
+
+    fn serialize(&self, dst: &mut Vec<u8>, _common_data: &CommonCircuitData<F, D>) -> IoResult<()> {
+        dst.write_usize(self.row)?;
+        self.gate.serialize(dst, _common_data)
+    }
+
+    fn deserialize(src: &mut Buffer, _common_data: &CommonCircuitData<F, D>) -> IoResult<Self> {
+        let row = src.read_usize()?;
+        let gate = SimpleExpLowDegreeGate::deserialize(src, _common_data)?;
+        Ok(Self {row, gate, _phatom: PhantomData})
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use anyhow::Result;
+
+    use plonky2::gates::gate_testing::{test_eval_fns, test_low_degree};
+    use plonky2::field::goldilocks_field::GoldilocksField;
+    use plonky2::field::types::{Field, Sample};
+    use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use plonky2::iop::witness::PartialWitness;
+
+    use crate::analysis::degree::measure_degree;
+
+    #[test]
+    fn low_degree() {
+        // Unlike `SimpleExpTestGate`, the maximum power is fine here as the degree stays 2.
+        let config = CircuitConfig::standard_recursion_config();
+        let gate = SimpleExpLowDegreeGate::new(SimpleExpLowDegreeGate::max_power(&config), &config);
+        test_low_degree::<GoldilocksField, _, 4>(gate);
+    }
+
+    #[test]
+    fn measured_degree() {
+        let config = CircuitConfig::standard_recursion_config();
+        let gate = SimpleExpLowDegreeGate::new(SimpleExpLowDegreeGate::max_power(&config), &config);
+        let report = measure_degree::<GoldilocksField, _, 4>(&gate, &config);
+        assert!(report.is_ok(), "{report}");
+    }
+
+    #[test]
+    fn eval_fns() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        let config = CircuitConfig::standard_recursion_config();
+        let gate = SimpleExpLowDegreeGate::new(SimpleExpLowDegreeGate::max_power(&config), &config);
+        test_eval_fns::<F, C, _, D>(gate)
+    }
+
+    #[test]
+    fn test_gate() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config.clone());
+
+        let power = SimpleExpLowDegreeGate::max_power(&config);
+        let gate = SimpleExpLowDegreeGate::new(power, &config);
+        let row = builder.add_gate(gate.clone(), vec![]);
+
+        let base = builder.add_virtual_target();
+        builder.connect(base, Target::wire(row, gate.wire_base()));
+        let output = Target::wire(row, gate.wire_output());
+        builder.register_public_input(output);
+
+        let circuit = builder.build::<C>();
+
+        // Only `base` is set, the generator fills in every power.
+        let base_value = F::rand();
+        let mut pw = PartialWitness::new();
+        pw.set_target(base, base_value);
+
+        let proof = circuit.prove(pw)?;
+        assert_eq!(proof.public_inputs[0], base_value.exp_u64(power as u64));
+
+        circuit.verify(proof)
+    }
+}
@@ -21,6 +21,8 @@ use plonky2::plonk::vars::{
 };
 use plonky2::util::serialization::{Buffer, IoResult, Read, Write};
 
+use crate::gates::layout::{GateLayout, Wire, Wires};
+
 #[derive(Debug, Clone, Default)]
 pub struct SimpleExpTestGate {
     pub num_limbs: usize
@@ -36,7 +38,7 @@ impl SimpleExpTestGate {
     }
 
     /// Determine the maximum number of operations that can fit in one gate for the given config.
-    fn max_power(config: &CircuitConfig) -> usize {
+    pub fn max_power(config: &CircuitConfig) -> usize {
         // 3 wires are reserved for the 0 power, base and output.
         let max_for_routed_wires = config.num_routed_wires - 3;
         let max_for_wires = (config.num_wires - 3) / 2;
@@ -45,7 +47,7 @@ impl SimpleExpTestGate {
 
     // the value of the first target must be `1`
     pub fn wire_base(&self) -> usize {
-        0
+        self.wire_ith_base(0)
     }
 
     /// The `i`th power of the exponent.
@@ -53,14 +55,24 @@ impl SimpleExpTestGate {
         debug_assert!(i <= self.num_limbs);
         debug_assert!(i > 0);
 
-        i
+        self.wire_ith_power(0, i - 1)
     }
 
     pub fn wire_output(&self) -> usize {
-        self.num_limbs + 1
+        self.wire_ith_output(0)
     }
 }
 
+// 1 for `base`, `num_limbs` for powers, 1 for `output`
+#[derive(GateLayout)]
+#[gate_layout(gate = SimpleExpTestGate)]
+pub struct SimpleExpTestLayout {
+    pub base: Wire,
+    #[gate_layout(len = num_limbs)]
+    pub power: Wires,
+    pub output: Wire,
+}
+
 impl<F: RichField + Extendable<D>, const D: usize> Gate<F, D> for SimpleExpTestGate {
     fn id(&self) -> String {
         format!("{self:?}")
@@ -155,15 +167,14 @@ impl<F: RichField + Extendable<D>, const D: usize> Gate<F, D> for SimpleExpTestG
 
     // 1 for `base`, `num_limbs` for powers, 1 for `output`
     fn num_wires(&self) -> usize {
-        self.num_limbs + 2
+        SimpleExpTestGate::num_wires(self)
     }
 
     fn num_constants(&self) -> usize {
         0
     }
 
-    // how to determine the `degree`?
-    // the `degree` is determined by the whole connected circuits of the gate
+    // the last power is `base^num_limbs`, see `tests::measured_degree`
     fn degree(&self) -> usize {
         self.num_limbs
     }
@@ -214,19 +225,9 @@ impl<F: RichField + Extendable<D>, const D: usize> SimpleGenerator<F, D>
         format!("{self:?}")
     }
 
+    // Only `base` is an input, the powers are written by this generator
     fn dependencies(&self) -> Vec<Target> {
-        let local_target = |column: usize| -> Target {
-            Target::wire(self.row, column)
-        };
-
-        let mut deps = Vec::with_capacity(self.gate.num_limbs + 1);
-        deps.push(local_target(self.gate.wire_base()));
-
-        for i in 1..(self.gate.num_limbs + 1) {
-            deps.push(local_target(i));
-        }
-
-        deps
+        vec![Target::wire(self.row, self.gate.wire_base())]
     }
 
     fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
@@ -275,6 +276,8 @@ mod tests {
     use plonky2::field::goldilocks_field::GoldilocksField;
     use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
 
+    use crate::analysis::degree::measure_degree;
+
     #[test]
     fn low_degree() {
         // Here the input parameter `power` cannot be very large, for example `1000` will take a long time.
@@ -283,6 +286,14 @@ mod tests {
         test_low_degree::<GoldilocksField, _, 4>(gate);
     }
 
+    #[test]
+    fn measured_degree() {
+        let config = CircuitConfig::standard_recursion_config();
+        let gate = SimpleExpTestGate::new(8, &config);
+        let report = measure_degree::<GoldilocksField, _, 4>(&gate, &config);
+        assert!(report.is_ok(), "{report}");
+    }
+
     #[test]
     fn eval_fns() -> Result<()> {
         const D: usize = 2;
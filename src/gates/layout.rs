@@ -0,0 +1,72 @@
+pub use gate_layout_derive::GateLayout;
+
+/// One wire of an op, see `GateLayout`.
+pub struct Wire;
+
+/// Consecutive wires of an op, their number is given by `#[gate_layout(len = <gate field>)]`.
+pub struct Wires;
+
+/// One constant of the gate.
+pub struct Constant;
+
+#[cfg(test)]
+mod tests {
+    use plonky2::plonk::circuit_data::CircuitConfig;
+
+    use crate::gates::g_w_v_v_constant::SimpleExpConstantGate;
+    use crate::gates::gate_with_veriable_vars::SimpleExpTestGate;
+    use crate::gates::simple_add_gate::SimpleMulAddTestGate;
+
+    // The expected indices are the hand-written `wire_*` functions of the gates before they used `GateLayout`.
+
+    #[test]
+    fn mul_add() {
+        let config = CircuitConfig::standard_recursion_config();
+        let gate = SimpleMulAddTestGate::new_from_config(&config);
+
+        assert_eq!(SimpleMulAddTestGate::wires_per_op(), 6);
+        assert_eq!(gate.num_ops, config.num_routed_wires / 6);
+        assert_eq!(gate.num_wires(), gate.num_ops * 6);
+        assert_eq!(SimpleMulAddTestGate::layout_num_constants(), 0);
+        for i in 0..gate.num_ops {
+            assert_eq!(SimpleMulAddTestGate::wire_ith_multiplicand_0(i), 6 * i);
+            assert_eq!(SimpleMulAddTestGate::wire_ith_multiplicand_1(i), 6 * i + 1);
+            assert_eq!(SimpleMulAddTestGate::wire_ith_add_1(i), 6 * i + 2);
+            assert_eq!(SimpleMulAddTestGate::wire_ith_add_2(i), 6 * i + 3);
+            assert_eq!(SimpleMulAddTestGate::wire_ith_addend(i), 6 * i + 4);
+            assert_eq!(SimpleMulAddTestGate::wire_ith_output(i), 6 * i + 5);
+        }
+    }
+
+    #[test]
+    fn exp() {
+        let config = CircuitConfig::standard_recursion_config();
+        for power in 1..SimpleExpTestGate::max_power(&config) {
+            let gate = SimpleExpTestGate::new(power, &config);
+
+            assert_eq!(gate.wire_base(), 0);
+            for i in 1..=power {
+                assert_eq!(gate.wire_power_i(i), i);
+            }
+            assert_eq!(gate.wire_output(), power + 1);
+            assert_eq!(gate.num_wires(), power + 2);
+        }
+        assert_eq!(SimpleExpTestGate::layout_num_constants(), 0);
+    }
+
+    #[test]
+    fn exp_constant() {
+        let config = CircuitConfig::standard_recursion_config();
+        for power in 1..SimpleExpConstantGate::max_power(&config) {
+            let gate = SimpleExpConstantGate::new(power, &config);
+
+            for i in 1..=power {
+                assert_eq!(gate.wire_power_i(i), i - 1);
+            }
+            assert_eq!(gate.wire_output(), power);
+            assert_eq!(gate.num_wires(), power + 1);
+        }
+        assert_eq!(SimpleExpConstantGate::const_base(), 0);
+        assert_eq!(SimpleExpConstantGate::layout_num_constants(), 1);
+    }
+}
@@ -1,3 +1,8 @@
+pub mod layout;
 pub mod simple_add_gate;
 pub mod gate_with_veriable_vars;
-pub mod g_w_v_v_constant;
\ No newline at end of file
+pub mod g_w_v_v_constant;
+pub mod g_w_v_v_low_degree;
+pub mod exp_var_gate;
+pub mod simple_add_ext_gate;
+pub mod expression_gate;
\ No newline at end of file
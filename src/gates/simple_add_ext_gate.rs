@@ -0,0 +1,315 @@
+#![allow(incomplete_features)]
+
+use core::marker::PhantomData;
+use core::ops::Range;
+
+use plonky2::field::extension::{Extendable, FieldExtension};
+use plonky2::gates::gate::Gate;
+use plonky2::gates::util::StridedConstraintConsumer;
+use plonky2::hash::hash_types::RichField;
+use plonky2::iop::ext_target::ExtensionTarget;
+use plonky2::iop::generator::{GeneratedValues, SimpleGenerator, WitnessGeneratorRef};
+use plonky2::iop::target::Target;
+use plonky2::iop::witness::{PartitionWitness, Witness, WitnessWrite};
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+use plonky2::plonk::circuit_data::{CircuitConfig, CommonCircuitData};
+use plonky2::plonk::vars::{EvaluationTargets, EvaluationVars, EvaluationVarsBase};
+use plonky2::util::serialization::{Buffer, IoResult, Read, Write};
+
+// The extension field version of `SimpleMulAddTestGate`, which can perform `x1 * x2 + x3 + x4 + x5` over `F::Extension`.
+// Every operand takes `D` wires.
+#[derive(Debug, Clone)]
+pub struct SimpleMulAddExtensionGate<const D: usize> {
+    pub num_ops: usize,
+}
+
+impl<const D: usize> SimpleMulAddExtensionGate<D> {
+    pub fn new_from_config(config: &CircuitConfig) -> Self {
+        Self {
+            num_ops: Self::num_ops(config),
+        }
+    }
+
+    /// Determine the maximum number of operations that can fit in one gate for the given config.
+    pub(crate) fn num_ops(config: &CircuitConfig) -> usize {
+        config.num_routed_wires / Self::wires_per_op()
+    }
+
+    // 5 extension inputs and 1 extension output
+    pub fn wires_per_op() -> usize {
+        6 * D
+    }
+
+    fn wires_ith_operand(i: usize, j: usize) -> Range<usize> {
+        let start = Self::wires_per_op() * i + D * j;
+        start..start + D
+    }
+
+    pub fn wires_ith_multiplicand_0(i: usize) -> Range<usize> {
+        Self::wires_ith_operand(i, 0)
+    }
+    pub fn wires_ith_multiplicand_1(i: usize) -> Range<usize> {
+        Self::wires_ith_operand(i, 1)
+    }
+    pub fn wires_ith_add_1(i: usize) -> Range<usize> {
+        Self::wires_ith_operand(i, 2)
+    }
+    pub fn wires_ith_add_2(i: usize) -> Range<usize> {
+        Self::wires_ith_operand(i, 3)
+    }
+    pub fn wires_ith_addend(i: usize) -> Range<usize> {
+        Self::wires_ith_operand(i, 4)
+    }
+    pub fn wires_ith_output(i: usize) -> Range<usize> {
+        Self::wires_ith_operand(i, 5)
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> Gate<F, D> for SimpleMulAddExtensionGate<D> {
+    fn id(&self) -> String {
+        format!("{self:?}")
+    }
+
+    fn serialize(&self, dst: &mut Vec<u8>, _common_data: &CommonCircuitData<F, D>) -> IoResult<()> {
+        dst.write_usize(self.num_ops)
+    }
+
+    fn deserialize(src: &mut Buffer, _common_data: &CommonCircuitData<F, D>) -> IoResult<Self> {
+        let num_ops = src.read_usize()?;
+        Ok(Self { num_ops })
+    }
+
+    fn eval_unfiltered(&self, vars: EvaluationVars<F, D>) -> Vec<F::Extension> {
+        let mut constraints = Vec::with_capacity(self.num_ops * D);
+        for i in 0..self.num_ops {
+            let multiplicand_0 = vars.get_local_ext_algebra(Self::wires_ith_multiplicand_0(i));
+            let multiplicand_1 = vars.get_local_ext_algebra(Self::wires_ith_multiplicand_1(i));
+            let add_1 = vars.get_local_ext_algebra(Self::wires_ith_add_1(i));
+            let add_2 = vars.get_local_ext_algebra(Self::wires_ith_add_2(i));
+            let addend = vars.get_local_ext_algebra(Self::wires_ith_addend(i));
+            let output = vars.get_local_ext_algebra(Self::wires_ith_output(i));
+
+            let computed_output = multiplicand_0 * multiplicand_1 + add_1 + add_2 + addend;
+
+            constraints.extend((output - computed_output).to_basefield_array());
+        }
+
+        constraints
+    }
+
+    fn eval_unfiltered_base_one(
+        &self,
+        vars: EvaluationVarsBase<F>,
+        mut yield_constr: StridedConstraintConsumer<F>,
+    ) {
+        for i in 0..self.num_ops {
+            let multiplicand_0 = vars.get_local_ext(Self::wires_ith_multiplicand_0(i));
+            let multiplicand_1 = vars.get_local_ext(Self::wires_ith_multiplicand_1(i));
+            let add_1 = vars.get_local_ext(Self::wires_ith_add_1(i));
+            let add_2 = vars.get_local_ext(Self::wires_ith_add_2(i));
+            let addend = vars.get_local_ext(Self::wires_ith_addend(i));
+            let output = vars.get_local_ext(Self::wires_ith_output(i));
+
+            let computed_output = multiplicand_0 * multiplicand_1 + add_1 + add_2 + addend;
+
+            yield_constr.many((output - computed_output).to_basefield_array());
+        }
+    }
+
+    fn eval_unfiltered_circuit(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: EvaluationTargets<D>,
+    ) -> Vec<ExtensionTarget<D>> {
+        let mut constraints = Vec::with_capacity(self.num_ops * D);
+        for i in 0..self.num_ops {
+            let multiplicand_0 = vars.get_local_ext_algebra(Self::wires_ith_multiplicand_0(i));
+            let multiplicand_1 = vars.get_local_ext_algebra(Self::wires_ith_multiplicand_1(i));
+            let add_1 = vars.get_local_ext_algebra(Self::wires_ith_add_1(i));
+            let add_2 = vars.get_local_ext_algebra(Self::wires_ith_add_2(i));
+            let addend = vars.get_local_ext_algebra(Self::wires_ith_addend(i));
+            let output = vars.get_local_ext_algebra(Self::wires_ith_output(i));
+
+            let computed_output = {
+                let rst = builder.mul_add_ext_algebra(multiplicand_0, multiplicand_1, add_1);
+                let rst = builder.add_ext_algebra(rst, add_2);
+                builder.add_ext_algebra(rst, addend)
+            };
+
+            let diff = builder.sub_ext_algebra(output, computed_output);
+            constraints.extend(diff.to_ext_target_array());
+        }
+
+        constraints
+    }
+
+    fn generators(&self, row: usize, _local_constants: &[F]) -> Vec<WitnessGeneratorRef<F, D>> {
+        (0..self.num_ops)
+            .map(|i| {
+                WitnessGeneratorRef::new(
+                    SimpleMulAddExtensionGenerator {
+                        row,
+                        i,
+                        _phantom_data: PhantomData
+                    }
+                    .adapter(),
+                )
+            })
+            .collect()
+    }
+
+    fn num_wires(&self) -> usize {
+        self.num_ops * Self::wires_per_op()
+    }
+
+    fn num_constants(&self) -> usize {
+        0
+    }
+
+    fn degree(&self) -> usize {
+        2
+    }
+
+    fn num_constraints(&self) -> usize {
+        self.num_ops * D
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SimpleMulAddExtensionGenerator<F: RichField + Extendable<D>, const D: usize> {
+    row: usize,
+    i: usize,
+    _phantom_data: PhantomData<F>
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> SimpleGenerator<F, D>
+    for SimpleMulAddExtensionGenerator<F, D> {
+
+    fn id(&self) -> String {
+        format!("{self:?}")
+    }
+
+    fn dependencies(&self) -> Vec<Target> {
+        SimpleMulAddExtensionGate::<D>::wires_ith_multiplicand_0(self.i)
+            .chain(SimpleMulAddExtensionGate::<D>::wires_ith_multiplicand_1(self.i))
+            .chain(SimpleMulAddExtensionGate::<D>::wires_ith_add_1(self.i))
+            .chain(SimpleMulAddExtensionGate::<D>::wires_ith_add_2(self.i))
+            .chain(SimpleMulAddExtensionGate::<D>::wires_ith_addend(self.i))
+            .map(|i| Target::wire(self.row, i))
+            .collect()
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let get_extension = |range: Range<usize>| -> F::Extension {
+            witness.get_extension_target(ExtensionTarget::from_range(self.row, range))
+        };
+
+        let multiplicand_0 = get_extension(SimpleMulAddExtensionGate::<D>::wires_ith_multiplicand_0(self.i));
+        let multiplicand_1 = get_extension(SimpleMulAddExtensionGate::<D>::wires_ith_multiplicand_1(self.i));
+        let add_1 = get_extension(SimpleMulAddExtensionGate::<D>::wires_ith_add_1(self.i));
+        let add_2 = get_extension(SimpleMulAddExtensionGate::<D>::wires_ith_add_2(self.i));
+        let addend = get_extension(SimpleMulAddExtensionGate::<D>::wires_ith_addend(self.i));
+
+        let output_target = ExtensionTarget::from_range(
+            self.row,
+            SimpleMulAddExtensionGate::<D>::wires_ith_output(self.i),
+        );
+
+        let computed_output =
+            multiplicand_0 * multiplicand_1 + add_1 + add_2 + addend;
+
+        out_buffer.set_extension_target(output_target, computed_output)
+    }
+
+    fn serialize(&self, dst: &mut Vec<u8>, _common_data: &CommonCircuitData<F, D>) -> IoResult<()> {
+        dst.write_usize(self.row)?;
+        dst.write_usize(self.i)
+    }
+
+    fn deserialize(src: &mut Buffer, _common_data: &CommonCircuitData<F, D>) -> IoResult<Self> {
+        let row = src.read_usize()?;
+        let i = src.read_usize()?;
+        Ok(Self {
+            row,
+            i,
+            _phantom_data: PhantomData
+        })
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use anyhow::Result;
+
+    use plonky2::field::types::Sample;
+    use plonky2::iop::witness::PartialWitness;
+    use plonky2::gates::gate_testing::{test_eval_fns, test_low_degree};
+    use plonky2::field::goldilocks_field::GoldilocksField;
+    use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+
+    use crate::analysis::degree::measure_degree;
+    use crate::builder::CookBuilderExt;
+
+    #[test]
+    fn low_degree() {
+        let gate = SimpleMulAddExtensionGate::new_from_config(&CircuitConfig::standard_recursion_config());
+        test_low_degree::<GoldilocksField, _, 4>(gate);
+    }
+
+    #[test]
+    fn measured_degree() {
+        let config = CircuitConfig::standard_recursion_config();
+        let gate = SimpleMulAddExtensionGate::new_from_config(&config);
+        let report = measure_degree::<GoldilocksField, _, 4>(&gate, &config);
+        assert!(report.is_ok(), "{report}");
+    }
+
+    #[test]
+    fn eval_fns() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        let gate = SimpleMulAddExtensionGate::new_from_config(&CircuitConfig::standard_recursion_config());
+        test_eval_fns::<F, C, _, D>(gate)
+    }
+
+    #[test]
+    fn mul_add3_extension() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type FF = <C as GenericConfig<D>>::FE;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let num_ops = SimpleMulAddExtensionGate::<D>::num_ops(&config);
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let mut inputs = Vec::new();
+        let mut outputs = Vec::new();
+        // Fill one row and start a second one.
+        for _ in 0..(num_ops + 1) {
+            let targets: Vec<ExtensionTarget<D>> =
+                (0..5).map(|_| builder.add_virtual_extension_target()).collect();
+            let output = builder.mul_add3_extension(targets[0], targets[1], targets[2], targets[3], targets[4]);
+            inputs.push(targets);
+            outputs.push(output);
+        }
+
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        for (targets, &output) in inputs.iter().zip(outputs.iter()) {
+            let values = FF::rand_vec(5);
+            for (&t, &v) in targets.iter().zip(values.iter()) {
+                pw.set_extension_target(t, v);
+            }
+            pw.set_extension_target(output, values[0] * values[1] + values[2] + values[3] + values[4]);
+        }
+
+        let proof = data.prove(pw)?;
+        data.verify(proof)
+    }
+}
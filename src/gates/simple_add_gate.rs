@@ -21,6 +21,8 @@ use plonky2::plonk::vars::{
 };
 use plonky2::util::serialization::{Buffer, IoResult, Read, Write};
 
+use crate::gates::layout::{GateLayout, Wire};
+
 // A test gate whick can perform `x1 * x2 + x3 + x4 + x5`
 #[derive(Debug, Clone)]
 pub struct SimpleMulAddTestGate {
@@ -33,36 +35,19 @@ impl SimpleMulAddTestGate {
             num_ops: Self::num_ops(config),
         }
     }
+}
 
-    /// Determine the maximum number of operations that can fit in one gate for the given config.
-    pub(crate) fn num_ops(config: &CircuitConfig) -> usize {
-        config.num_routed_wires / Self::wires_per_op()
-    }
-
-    // We can treat wires as variables and outputs
-    pub fn wires_per_op() -> usize {
-        6
-    }
-
-    // get the index of 
-    pub fn wire_ith_multiplicand_0(i: usize) -> usize {
-        Self::wires_per_op() * i
-    }
-    pub fn wire_ith_multiplicand_1(i: usize) -> usize {
-        Self::wires_per_op() * i + 1
-    }
-    pub fn wire_ith_add_1(i: usize) -> usize {
-        Self::wires_per_op() * i + 2
-    }
-    pub fn wire_ith_add_2(i: usize) -> usize {
-        Self::wires_per_op() * i + 3
-    }
-    pub fn wire_ith_addend(i: usize) -> usize {
-        Self::wires_per_op() * i + 4
-    }
-    pub fn wire_ith_output(i: usize) -> usize {
-        Self::wires_per_op() * i + 5
-    }
+// We can treat wires as variables and outputs.
+// The index functions such as `wire_ith_multiplicand_0`, `wires_per_op` and `num_ops` are generated from this layout.
+#[derive(GateLayout)]
+#[gate_layout(gate = SimpleMulAddTestGate, num_ops = num_ops)]
+pub struct SimpleMulAddTestLayout {
+    pub multiplicand_0: Wire,
+    pub multiplicand_1: Wire,
+    pub add_1: Wire,
+    pub add_2: Wire,
+    pub addend: Wire,
+    pub output: Wire,
 }
 
 impl<F: RichField + Extendable<D>, const D: usize> Gate<F, D> for SimpleMulAddTestGate {
@@ -152,15 +137,16 @@ impl<F: RichField + Extendable<D>, const D: usize> Gate<F, D> for SimpleMulAddTe
     }
 
     fn num_wires(&self) -> usize {
-        self.num_ops * Self::wires_per_op()
+        SimpleMulAddTestGate::num_wires(self)
     }
 
     fn num_constants(&self) -> usize {
         0
     }
 
+    // `multiplicand_0 * multiplicand_1`, the additions do not raise the degree
     fn degree(&self) -> usize {
-        3
+        2
     }
 
     fn num_constraints(&self) -> usize {
@@ -263,6 +249,9 @@ mod tests {
     use plonky2::field::goldilocks_field::GoldilocksField;
     use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
 
+    use crate::analysis::degree::measure_degree;
+    use crate::labels::LabelledBuilder;
+
     #[test]
     fn targets_test() {
         let mut log_builder = env_logger::Builder::from_default_env();
@@ -276,7 +265,7 @@ mod tests {
 
         let config = CircuitConfig::standard_recursion_config();
 
-        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let mut builder = LabelledBuilder::<F, D>::new(config);
 
         let x = builder.add_virtual_target();
         let y = builder.add_virtual_target();
@@ -287,6 +276,11 @@ mod tests {
         let oxy = builder.add(oxy, xx);
         let oxy = builder.add(oxy, yy);
         let output = builder.add(oxy, z);
+        builder.label(x, "x");
+        builder.label(y, "y");
+        builder.label(z, "z");
+        builder.label(oxy, "oxy");
+        builder.label(output, "output");
 
         info!("before build row `x`: {:?}", x);
         info!("before build row `y`: {:?}", y);
@@ -296,7 +290,8 @@ mod tests {
 
         let x_value = F::rand();
 
-        let data = builder.build::<C>();
+        let (data, table) = builder.build::<C>();
+        info!("after build:\n{table}");
         info!("degree: {}", data.common.constraint_degree());
         info!("the number of gates is: {}", data.common.gates.len());
 
@@ -320,6 +315,14 @@ mod tests {
         test_low_degree::<GoldilocksField, _, 4>(gate);
     }
 
+    #[test]
+    fn measured_degree() {
+        let config = CircuitConfig::standard_recursion_config();
+        let gate = SimpleMulAddTestGate::new_from_config(&config);
+        let report = measure_degree::<GoldilocksField, _, 4>(&gate, &config);
+        assert!(report.is_ok(), "{report}");
+    }
+
     #[test]
     fn eval_fns() -> Result<()> {
         const D: usize = 2;
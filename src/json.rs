@@ -0,0 +1,434 @@
+//! JSON encoding of proofs and verifier data for services outside of Rust.
+//!
+//! - Field elements are canonical decimal strings, as most JSON parsers lose precision on 64-bit numbers.
+//!   Extension field elements are arrays of their base field coefficients.
+//! - Hashes, in the proof and in the verifier data, are hex strings of `GenericHashOut::to_bytes`.
+//! - `CommonCircuitData` is only exported as a readable summary, verifying needs the binary encoding.
+//!
+//! Only the parts of a proof made of field elements alone, e.g. the `OpeningSet`, go through serde, the
+//! hashes are encoded one by one, so no other number or string is ever taken for a field element.
+
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+
+use plonky2::field::extension::Extendable;
+use plonky2::field::types::Field64;
+use plonky2::fri::proof::{FriInitialTreeProof, FriProof, FriQueryRound, FriQueryStep};
+use plonky2::hash::hash_types::RichField;
+use plonky2::hash::merkle_proofs::MerkleProof;
+use plonky2::hash::keccak::KeccakHash;
+use plonky2::hash::merkle_tree::MerkleCap;
+use plonky2::hash::poseidon::PoseidonHash;
+use plonky2::plonk::circuit_data::{CommonCircuitData, VerifierOnlyCircuitData};
+use plonky2::plonk::config::{GenericConfig, GenericHashOut, Hasher};
+use plonky2::plonk::proof::{Proof, ProofWithPublicInputs};
+
+/// `value` must be the serde encoding of field elements alone, e.g. an `OpeningSet`: every number is one of them.
+fn field_elements_to_strings<F: Field64>(value: Value) -> Value {
+    match value {
+        Value::Number(n) => {
+            let n = n.as_u64().expect("a field element is serialized as a u64");
+            Value::String((n % F::ORDER).to_string())
+        }
+        Value::Array(values) => Value::Array(values.into_iter().map(field_elements_to_strings::<F>).collect()),
+        Value::Object(map) => Value::Object(map.into_iter().map(|(k, v)| (k, field_elements_to_strings::<F>(v))).collect()),
+        value => value,
+    }
+}
+
+/// The inverse of `field_elements_to_strings`, every leaf must be a canonical decimal string.
+fn field_elements_from_strings<F: Field64>(value: &Value) -> Result<Value> {
+    Ok(match value {
+        Value::String(s) => {
+            let n = s
+                .parse::<u64>()
+                .ok()
+                .filter(|&n| n < F::ORDER)
+                .ok_or_else(|| anyhow!("`{s}` is not a canonical field element"))?;
+            Value::Number(n.into())
+        }
+        Value::Array(values) => Value::Array(
+            values
+                .iter()
+                .map(field_elements_from_strings::<F>)
+                .collect::<Result<_>>()?,
+        ),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(k, v)| -> Result<(String, Value)> { Ok((k.clone(), field_elements_from_strings::<F>(v)?)) })
+                .collect::<Result<_>>()?,
+        ),
+        value => return Err(anyhow!("expected a field element as a decimal string, found {value}")),
+    })
+}
+
+fn array(value: &Value) -> Result<&Vec<Value>> {
+    value.as_array().ok_or_else(|| anyhow!("expected an array, found {value}"))
+}
+
+/// A hasher whose hashes are read back from hex.
+pub trait HexHasher<F: RichField>: Hasher<F> {
+    /// Whether every 8 bytes of a hash are a little-endian field element, which `from_bytes` takes as canonical.
+    const FIELD_ELEMENTS: bool;
+}
+
+impl<F: RichField> HexHasher<F> for PoseidonHash {
+    const FIELD_ELEMENTS: bool = true;
+}
+
+impl<F: RichField, const N: usize> HexHasher<F> for KeccakHash<N> {
+    const FIELD_ELEMENTS: bool = false;
+}
+
+fn hash_to_hex<F: RichField, H: Hasher<F>>(hash: &H::Hash) -> Value {
+    Value::String(hex::encode(hash.to_bytes()))
+}
+
+fn hash_from_hex<F: RichField, H: HexHasher<F>>(value: &Value) -> Result<H::Hash> {
+    let s = value.as_str().ok_or_else(|| anyhow!("expected a hex string, found {value}"))?;
+    let bytes = hex::decode(s)?;
+    if bytes.len() != H::HASH_SIZE {
+        return Err(anyhow!("a hash has {} bytes, `{s}` has {}", H::HASH_SIZE, bytes.len()));
+    }
+    // `from_bytes` panics on a non-canonical limb in debug builds, and keeps it in release builds.
+    if H::FIELD_ELEMENTS {
+        for limb in bytes.chunks(8) {
+            let limb = u64::from_le_bytes(limb.try_into().expect("HASH_SIZE is a multiple of 8"));
+            if limb >= F::ORDER {
+                return Err(anyhow!("`{s}` has the non-canonical field element {limb}"));
+            }
+        }
+    }
+    Ok(H::Hash::from_bytes(&bytes))
+}
+
+fn cap_to_json<F: RichField, H: Hasher<F>>(cap: &MerkleCap<F, H>) -> Value {
+    Value::Array(cap.0.iter().map(hash_to_hex::<F, H>).collect())
+}
+
+fn cap_from_json<F: RichField, H: HexHasher<F>>(value: &Value) -> Result<MerkleCap<F, H>> {
+    Ok(MerkleCap(array(value)?.iter().map(hash_from_hex::<F, H>).collect::<Result<_>>()?))
+}
+
+fn merkle_proof_to_json<F: RichField, H: Hasher<F>>(proof: &MerkleProof<F, H>) -> Value {
+    json!({ "siblings": proof.siblings.iter().map(hash_to_hex::<F, H>).collect::<Vec<_>>() })
+}
+
+fn merkle_proof_from_json<F: RichField, H: HexHasher<F>>(value: &Value) -> Result<MerkleProof<F, H>> {
+    Ok(MerkleProof {
+        siblings: array(&value["siblings"])?.iter().map(hash_from_hex::<F, H>).collect::<Result<_>>()?,
+    })
+}
+
+pub fn proof_to_json<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    proof: &ProofWithPublicInputs<F, C, D>,
+) -> Result<String> {
+    let fields = |value: Value| field_elements_to_strings::<F>(value);
+    let fri = &proof.proof.opening_proof;
+    let query_round_proofs: Vec<Value> = fri
+        .query_round_proofs
+        .iter()
+        .map(|round| -> Result<Value> {
+            let evals_proofs: Vec<Value> = round
+                .initial_trees_proof
+                .evals_proofs
+                .iter()
+                .map(|(evals, merkle_proof)| -> Result<Value> {
+                    Ok(json!([fields(serde_json::to_value(evals)?), merkle_proof_to_json(merkle_proof)]))
+                })
+                .collect::<Result<_>>()?;
+            let steps: Vec<Value> = round
+                .steps
+                .iter()
+                .map(|step| -> Result<Value> {
+                    Ok(json!({
+                        "evals": fields(serde_json::to_value(&step.evals)?),
+                        "merkle_proof": merkle_proof_to_json(&step.merkle_proof),
+                    }))
+                })
+                .collect::<Result<_>>()?;
+            Ok(json!({ "initial_trees_proof": { "evals_proofs": evals_proofs }, "steps": steps }))
+        })
+        .collect::<Result<_>>()?;
+
+    let value = json!({
+        "proof": {
+            "wires_cap": cap_to_json(&proof.proof.wires_cap),
+            "plonk_zs_partial_products_cap": cap_to_json(&proof.proof.plonk_zs_partial_products_cap),
+            "quotient_polys_cap": cap_to_json(&proof.proof.quotient_polys_cap),
+            "openings": fields(serde_json::to_value(&proof.proof.openings)?),
+            "opening_proof": {
+                "commit_phase_merkle_caps": fri.commit_phase_merkle_caps.iter().map(cap_to_json).collect::<Vec<_>>(),
+                "query_round_proofs": query_round_proofs,
+                "final_poly": fields(serde_json::to_value(&fri.final_poly)?),
+                "pow_witness": fields(serde_json::to_value(fri.pow_witness)?),
+            },
+        },
+        "public_inputs": fields(serde_json::to_value(&proof.public_inputs)?),
+    });
+    Ok(serde_json::to_string_pretty(&value)?)
+}
+
+pub fn proof_from_json<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    json: &str,
+) -> Result<ProofWithPublicInputs<F, C, D>>
+where
+    C::Hasher: HexHasher<F>,
+{
+    let value: Value = serde_json::from_str(json)?;
+    let fields = |value: &Value| field_elements_from_strings::<F>(value);
+    let proof = &value["proof"];
+    let fri = &proof["opening_proof"];
+
+    let query_round_proofs = array(&fri["query_round_proofs"])?
+        .iter()
+        .map(|round| -> Result<FriQueryRound<F, C::Hasher, D>> {
+            let evals_proofs = array(&round["initial_trees_proof"]["evals_proofs"])?
+                .iter()
+                .map(|pair| -> Result<(Vec<F>, MerkleProof<F, C::Hasher>)> {
+                    Ok((serde_json::from_value(fields(&pair[0])?)?, merkle_proof_from_json(&pair[1])?))
+                })
+                .collect::<Result<_>>()?;
+            let steps = array(&round["steps"])?
+                .iter()
+                .map(|step| -> Result<FriQueryStep<F, C::Hasher, D>> {
+                    Ok(FriQueryStep {
+                        evals: serde_json::from_value(fields(&step["evals"])?)?,
+                        merkle_proof: merkle_proof_from_json(&step["merkle_proof"])?,
+                    })
+                })
+                .collect::<Result<_>>()?;
+            Ok(FriQueryRound {
+                initial_trees_proof: FriInitialTreeProof { evals_proofs },
+                steps,
+            })
+        })
+        .collect::<Result<_>>()?;
+
+    Ok(ProofWithPublicInputs {
+        proof: Proof {
+            wires_cap: cap_from_json(&proof["wires_cap"])?,
+            plonk_zs_partial_products_cap: cap_from_json(&proof["plonk_zs_partial_products_cap"])?,
+            quotient_polys_cap: cap_from_json(&proof["quotient_polys_cap"])?,
+            openings: serde_json::from_value(fields(&proof["openings"])?)?,
+            opening_proof: FriProof {
+                commit_phase_merkle_caps: array(&fri["commit_phase_merkle_caps"])?
+                    .iter()
+                    .map(cap_from_json)
+                    .collect::<Result<_>>()?,
+                query_round_proofs,
+                final_poly: serde_json::from_value(fields(&fri["final_poly"])?)?,
+                pow_witness: serde_json::from_value(fields(&fri["pow_witness"])?)?,
+            },
+        },
+        public_inputs: serde_json::from_value(fields(&value["public_inputs"])?)?,
+    })
+}
+
+pub fn verifier_only_to_json<C: GenericConfig<D>, const D: usize>(data: &VerifierOnlyCircuitData<C, D>) -> String {
+    let value = json!({
+        "constants_sigmas_cap": data
+            .constants_sigmas_cap
+            .0
+            .iter()
+            .map(hash_to_hex::<C::F, C::Hasher>)
+            .collect::<Vec<_>>(),
+        "circuit_digest": hash_to_hex::<C::F, C::Hasher>(&data.circuit_digest),
+    });
+    serde_json::to_string_pretty(&value).expect("a JSON value is always serializable")
+}
+
+pub fn verifier_only_from_json<C: GenericConfig<D>, const D: usize>(json: &str) -> Result<VerifierOnlyCircuitData<C, D>>
+where
+    C::Hasher: HexHasher<C::F>,
+{
+    let value: Value = serde_json::from_str(json)?;
+    let cap = value["constants_sigmas_cap"]
+        .as_array()
+        .ok_or_else(|| anyhow!("missing `constants_sigmas_cap`"))?
+        .iter()
+        .map(hash_from_hex::<C::F, C::Hasher>)
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(VerifierOnlyCircuitData {
+        constants_sigmas_cap: MerkleCap(cap),
+        circuit_digest: hash_from_hex::<C::F, C::Hasher>(&value["circuit_digest"])?,
+    })
+}
+
+/// A readable summary of `common`, it can not be imported back.
+pub fn common_summary_json<F: RichField + Extendable<D>, const D: usize>(common: &CommonCircuitData<F, D>) -> String {
+    let config = &common.config;
+    let value = json!({
+        "degree_bits": common.degree_bits(),
+        "gates": common.gates.iter().map(|g| g.0.id()).collect::<Vec<_>>(),
+        "num_public_inputs": common.num_public_inputs,
+        "num_constants": common.num_constants,
+        "num_partial_products": common.num_partial_products,
+        "quotient_degree_factor": common.quotient_degree_factor,
+        "selectors": {
+            "selector_indices": common.selectors_info.selector_indices,
+            "groups": common.selectors_info.groups.iter().map(|r| [r.start, r.end]).collect::<Vec<_>>(),
+        },
+        "config": {
+            "num_wires": config.num_wires,
+            "num_routed_wires": config.num_routed_wires,
+            "num_constants": config.num_constants,
+            "use_base_arithmetic_gate": config.use_base_arithmetic_gate,
+            "security_bits": config.security_bits,
+            "num_challenges": config.num_challenges,
+            "zero_knowledge": config.zero_knowledge,
+            "max_quotient_degree_factor": config.max_quotient_degree_factor,
+            "fri_config": {
+                "rate_bits": config.fri_config.rate_bits,
+                "cap_height": config.fri_config.cap_height,
+                "proof_of_work_bits": config.fri_config.proof_of_work_bits,
+                "reduction_strategy": format!("{:?}", config.fri_config.reduction_strategy),
+                "num_query_rounds": config.fri_config.num_query_rounds,
+            },
+        },
+        "fri_params": {
+            "hiding": common.fri_params.hiding,
+            "degree_bits": common.fri_params.degree_bits,
+            "reduction_arity_bits": common.fri_params.reduction_arity_bits,
+        },
+    });
+    serde_json::to_string_pretty(&value).expect("a JSON value is always serializable")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use plonky2::field::types::PrimeField64;
+    use plonky2::plonk::circuit_data::VerifierCircuitData;
+    use plonky2::plonk::config::{KeccakGoldilocksConfig, PoseidonGoldilocksConfig};
+
+    use crate::examples::{Example, ExampleCircuit};
+
+    const D: usize = 2;
+    type F = <PoseidonGoldilocksConfig as GenericConfig<D>>::F;
+
+    fn prove<C: GenericConfig<D, F = F>>() -> Result<(ExampleCircuit<F, C, D>, ProofWithPublicInputs<F, C, D>)> {
+        let circuit = Example::MulAdd.build::<F, C, D>(1)?;
+        let values: Vec<(String, u64)> = ["a0", "b0", "c0", "d0", "e0"]
+            .iter()
+            .map(|n| (n.to_string(), u64::MAX - 1))
+            .collect();
+        let proof = circuit.data.prove(circuit.witness(&values)?)?;
+        Ok((circuit, proof))
+    }
+
+    fn round_trip<C: GenericConfig<D, F = F>>() -> Result<()>
+    where
+        C::Hasher: HexHasher<F>,
+    {
+        let (circuit, proof) = prove::<C>()?;
+
+        let proof_json = proof_to_json(&proof)?;
+        let verifier_json = verifier_only_to_json(&circuit.data.verifier_only);
+
+        let imported_proof = proof_from_json::<F, C, D>(&proof_json)?;
+        let imported_verifier = verifier_only_from_json::<C, D>(&verifier_json)?;
+        assert_eq!(imported_proof, proof);
+        assert_eq!(imported_verifier, circuit.data.verifier_only);
+
+        VerifierCircuitData {
+            verifier_only: imported_verifier,
+            common: circuit.data.common,
+        }
+        .verify(imported_proof)
+    }
+
+    #[test]
+    fn round_trip_poseidon() -> Result<()> {
+        round_trip::<PoseidonGoldilocksConfig>()
+    }
+
+    #[test]
+    fn round_trip_keccak() -> Result<()> {
+        round_trip::<KeccakGoldilocksConfig>()
+    }
+
+    #[test]
+    fn decimal_strings() -> Result<()> {
+        let (_, proof) = prove::<PoseidonGoldilocksConfig>()?;
+        let value: Value = serde_json::from_str(&proof_to_json(&proof)?)?;
+
+        let public_inputs: Vec<&str> = value["public_inputs"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|v| v.as_str().unwrap())
+            .collect();
+        assert_eq!(public_inputs, vec![proof.public_inputs[0].to_canonical_u64().to_string()]);
+        Ok(())
+    }
+
+    #[test]
+    fn hashes_are_hex() -> Result<()> {
+        // Keccak hashes are bytes, they must not be taken for field elements.
+        let (_, proof) = prove::<KeccakGoldilocksConfig>()?;
+        let value: Value = serde_json::from_str(&proof_to_json(&proof)?)?;
+
+        let cap = value["proof"]["wires_cap"][0].as_str().unwrap();
+        assert_eq!(hex::decode(cap)?, proof.proof.wires_cap.0[0].to_bytes());
+        Ok(())
+    }
+
+    #[test]
+    fn non_canonical_field_element() -> Result<()> {
+        let (_, proof) = prove::<PoseidonGoldilocksConfig>()?;
+
+        let mut value: Value = serde_json::from_str(&proof_to_json(&proof)?)?;
+        value["public_inputs"][0] = Value::String(F::ORDER.to_string());
+        assert!(proof_from_json::<F, PoseidonGoldilocksConfig, D>(&value.to_string()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn non_canonical_hash() -> Result<()> {
+        let (circuit, proof) = prove::<PoseidonGoldilocksConfig>()?;
+
+        // The first limb of a Poseidon hash is `u64::MAX`, above the field order.
+        let mut value: Value = serde_json::from_str(&proof_to_json(&proof)?)?;
+        let cap = value["proof"]["wires_cap"][0].as_str().unwrap().to_string();
+        value["proof"]["wires_cap"][0] = Value::String(format!("ffffffffffffffff{}", &cap[16..]));
+        assert!(proof_from_json::<F, PoseidonGoldilocksConfig, D>(&value.to_string()).is_err());
+
+        let mut value: Value = serde_json::from_str(&verifier_only_to_json(&circuit.data.verifier_only))?;
+        value["circuit_digest"] = Value::String("ff".repeat(32));
+        assert!(verifier_only_from_json::<PoseidonGoldilocksConfig, D>(&value.to_string()).is_err());
+
+        // The same bytes are a valid Keccak hash.
+        let (_, proof) = prove::<KeccakGoldilocksConfig>()?;
+        let mut value: Value = serde_json::from_str(&proof_to_json(&proof)?)?;
+        let size = proof.proof.wires_cap.0[0].to_bytes().len();
+        value["proof"]["wires_cap"][0] = Value::String("ff".repeat(size));
+        let read = proof_from_json::<F, KeccakGoldilocksConfig, D>(&value.to_string())?;
+        assert_eq!(read.proof.wires_cap.0[0].to_bytes(), vec![0xff; size]);
+        Ok(())
+    }
+
+    #[test]
+    fn tampered_public_input() -> Result<()> {
+        let (circuit, proof) = prove::<PoseidonGoldilocksConfig>()?;
+
+        let mut value: Value = serde_json::from_str(&proof_to_json(&proof)?)?;
+        value["public_inputs"][0] = Value::String("1".to_string());
+        let tampered = proof_from_json::<F, PoseidonGoldilocksConfig, D>(&value.to_string())?;
+
+        assert!(circuit.data.verify(tampered).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn summary() -> Result<()> {
+        let (circuit, _) = prove::<PoseidonGoldilocksConfig>()?;
+        let value: Value = serde_json::from_str(&common_summary_json(&circuit.data.common))?;
+
+        assert_eq!(value["degree_bits"], circuit.data.common.degree_bits());
+        assert_eq!(value["gates"].as_array().unwrap().len(), circuit.data.common.gates.len());
+        Ok(())
+    }
+}
@@ -0,0 +1,253 @@
+//! Names for targets which survive `build`.
+//!
+//! `LabelledBuilder` derefs to `CircuitBuilder`, so every builder method and `CookBuilderExt` helper works on it,
+//! and adds `label(target, name)`. Its `build` returns a `LabelTable` locating every label in the built circuit:
+//! the copy-constraint partition of the target, its representative, and the row, column and gate of the
+//! wire it landed on.
+
+use core::fmt;
+use core::ops::{Deref, DerefMut};
+
+use plonky2::field::extension::Extendable;
+use plonky2::hash::hash_types::RichField;
+use plonky2::iop::target::Target;
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+use plonky2::plonk::circuit_data::{CircuitConfig, CircuitData};
+use plonky2::plonk::config::GenericConfig;
+
+use crate::analysis::mock_prover::row_gates;
+
+pub struct LabelledBuilder<F: RichField + Extendable<D>, const D: usize> {
+    builder: CircuitBuilder<F, D>,
+    labels: Vec<(String, Target)>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> LabelledBuilder<F, D> {
+    pub fn new(config: CircuitConfig) -> Self {
+        Self::from_builder(CircuitBuilder::new(config))
+    }
+
+    pub fn from_builder(builder: CircuitBuilder<F, D>) -> Self {
+        Self {
+            builder,
+            labels: Vec::new(),
+        }
+    }
+
+    /// Names `target`. A target may have several names, but a name only one target.
+    pub fn label(&mut self, target: Target, name: &str) -> Target {
+        assert!(
+            self.labels.iter().all(|(n, _)| n != name),
+            "the label `{name}` is already used"
+        );
+        self.labels.push((name.to_string(), target));
+        target
+    }
+
+    pub fn labels(&self) -> &[(String, Target)] {
+        &self.labels
+    }
+
+    pub fn build<C: GenericConfig<D, F = F>>(self) -> (CircuitData<F, C, D>, LabelTable) {
+        let data = self.builder.build::<C>();
+        let table = locate_labels(&data, &self.labels);
+        (data, table)
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> Deref for LabelledBuilder<F, D> {
+    type Target = CircuitBuilder<F, D>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.builder
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> DerefMut for LabelledBuilder<F, D> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.builder
+    }
+}
+
+/// Where a labelled target landed in the built circuit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LabelLocation {
+    pub name: String,
+    /// The target as it was labelled, before build.
+    pub target: Target,
+    /// The representative of the copy-constraint partition, a wire or a virtual target.
+    pub representative: Target,
+    /// Every routed wire `(row, column)` of the partition, in row order.
+    pub partition: Vec<(usize, usize)>,
+    /// The wire holding the value: the representative if it is a wire, else the first wire of the partition.
+    /// `None` when the target is not copied to any wire, e.g. a virtual target which is only set by a generator.
+    pub wire: Option<(usize, usize)>,
+    /// The `id()` of the gate on the row of `wire`. plonky2 picks the representative from the order of the
+    /// `connect` calls, so this is often a gate consuming the value rather than the gate producing it.
+    pub gate: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LabelTable {
+    pub locations: Vec<LabelLocation>,
+}
+
+impl LabelTable {
+    pub fn get(&self, name: &str) -> Option<&LabelLocation> {
+        self.locations.iter().find(|l| l.name == name)
+    }
+
+    /// The wire of `name`, see `LabelLocation::wire`.
+    pub fn wire(&self, name: &str) -> Option<(usize, usize)> {
+        self.get(name).and_then(|l| l.wire)
+    }
+
+    /// The labels as they were taken on the builder.
+    pub fn labels(&self) -> Vec<(String, Target)> {
+        self.locations.iter().map(|l| (l.name.clone(), l.target)).collect()
+    }
+}
+
+impl fmt::Display for LabelTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:<16} {:<8} {:<8} {:<40} {:<10} representative", "label", "row", "column", "gate", "wires")?;
+        for l in self.locations.iter() {
+            let (row, column) = match l.wire {
+                Some((row, column)) => (row.to_string(), column.to_string()),
+                None => ("-".to_string(), "-".to_string()),
+            };
+            writeln!(
+                f,
+                "{:<16} {:<8} {:<8} {:<40} {:<10} {:?}",
+                l.name,
+                row,
+                column,
+                l.gate.as_deref().unwrap_or("-"),
+                l.partition.len(),
+                l.representative
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Locates `labels`, which were taken on the builder of `data`, in the built circuit.
+pub fn locate_labels<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    data: &CircuitData<F, C, D>,
+    labels: &[(String, Target)],
+) -> LabelTable {
+    let common = &data.common;
+    let (num_wires, degree) = (common.config.num_wires, common.degree());
+    let representative_map = &data.prover_only.representative_map;
+    let gates = row_gates(data);
+
+    let to_target = |index: usize| {
+        if index < degree * num_wires {
+            Target::wire(index / num_wires, index % num_wires)
+        } else {
+            Target::VirtualTarget {
+                index: index - degree * num_wires,
+            }
+        }
+    };
+
+    let locations = labels
+        .iter()
+        .map(|(name, target)| {
+            let representative = representative_map[target.index(num_wires, degree)];
+            let partition: Vec<(usize, usize)> = (0..degree)
+                .flat_map(|row| (0..common.config.num_routed_wires).map(move |column| (row, column)))
+                .filter(|&(row, column)| representative_map[row * num_wires + column] == representative)
+                .collect();
+
+            let wire = match to_target(representative) {
+                Target::Wire(w) => Some((w.row, w.column)),
+                Target::VirtualTarget { .. } => partition.first().copied(),
+            };
+
+            LabelLocation {
+                name: name.clone(),
+                target: *target,
+                representative: to_target(representative),
+                partition,
+                wire,
+                gate: wire.map(|(row, _)| common.gates[gates[row]].0.id()),
+            }
+        })
+        .collect();
+
+    LabelTable { locations }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use anyhow::Result;
+
+    use plonky2::field::types::Field;
+    use plonky2::iop::witness::{PartialWitness, WitnessWrite};
+    use plonky2::plonk::config::PoseidonGoldilocksConfig;
+
+    use crate::analysis::mock_prover::MockProver;
+    use crate::builder::CookBuilderExt;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    #[test]
+    fn locate() -> Result<()> {
+        let mut builder = LabelledBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+
+        let x = builder.add_virtual_target();
+        let y = builder.add_virtual_target();
+        let xy = builder.mul(x, y);
+        let output = builder.mul_add3(x, y, xy, xy, xy);
+        let unused = builder.add_virtual_target();
+        builder.label(x, "x");
+        builder.label(y, "y");
+        builder.label(xy, "xy");
+        builder.label(output, "output");
+        builder.label(unused, "unused");
+
+        let (data, table) = builder.build::<C>();
+
+        // plonky2's `mul(x, y)` is `arithmetic(1, 0, x, y, x)`, so `x` is copied to the multiplicand and the
+        // addend of the `ArithmeticGate`, and to the first multiplicand of the `SimpleMulAddTestGate`.
+        assert_eq!(table.get("x").unwrap().partition.len(), 3);
+        assert_eq!(table.get("y").unwrap().partition.len(), 2);
+        // `xy` is the `ArithmeticGate` output, copied to three operands of the `SimpleMulAddTestGate`.
+        // Every `connect(gate_wire, xy)` makes the gate wire the new representative, so the last one wins.
+        let xy_location = table.get("xy").unwrap();
+        assert_eq!(xy_location.partition.len(), 4);
+        assert!(xy_location.gate.as_ref().unwrap().starts_with("SimpleMulAddTestGate"));
+        assert!(table.get("output").unwrap().gate.as_ref().unwrap().starts_with("SimpleMulAddTestGate"));
+        assert_eq!(table.wire("unused"), None);
+
+        // The value of every label is on its wire.
+        let (x_value, y_value) = (F::from_canonical_u64(3), F::from_canonical_u64(5));
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, x_value);
+        pw.set_target(y, y_value);
+        let prover = MockProver::new(&data, pw)?;
+        let value = |name| {
+            let (row, column) = table.wire(name).unwrap();
+            prover.wires[row][column]
+        };
+        assert_eq!(value("x"), x_value);
+        assert_eq!(value("xy"), x_value * y_value);
+        assert_eq!(value("output"), x_value * y_value * F::from_canonical_u64(4));
+        Ok(())
+    }
+
+    #[test]
+    #[should_panic]
+    fn duplicate_label() {
+        let mut builder = LabelledBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_target();
+        let y = builder.add_virtual_target();
+        builder.label(x, "x");
+        builder.label(y, "x");
+    }
+}
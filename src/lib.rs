@@ -4,5 +4,14 @@
 #![allow(incomplete_features)]
 #![feature(generic_const_exprs)]
 
+pub mod aggregation;
+pub mod analysis;
+pub mod builder;
+pub mod bundle;
+pub mod examples;
 pub mod gates;
-pub mod libtests;
\ No newline at end of file
+pub mod json;
+pub mod labels;
+pub mod libtests;
+pub mod serialization;
+pub mod witness;
\ No newline at end of file
@@ -5,7 +5,7 @@ mod tests {
 
     use plonky2::iop::witness::{PartialWitness, WitnessWrite};
     use plonky2::plonk::circuit_builder::CircuitBuilder;
-    use plonky2::plonk::circuit_data::CircuitConfig;
+    use plonky2::plonk::circuit_data::{CircuitConfig, VerifierCircuitData};
     use plonky2::hash::hash_types::RichField;
     use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
 
@@ -14,6 +14,8 @@ mod tests {
 
     use plonky2_ecdsa::gadgets::recursive_proof::ProofTuple;
 
+    use crate::aggregation::aggregate;
+
     fn make_array_sum<
         F: RichField + Extendable<D>,
         C: GenericConfig<D, F = F>,
@@ -60,9 +62,13 @@ mod tests {
 
         let res_2 = make_array_sum::<F, C, D>(&vec![F::from_canonical_u64(1), F::from_canonical_u64(2)]).unwrap();
 
-        info!("proof 1: {:?}, verifier data 1: {:?}, common data 1: {:?}", res_1.0.public_inputs, 1, 1);
-        info!("proof 2: {:?}, verifier data 2: {:?}, common data 2: {:?}", res_2.0.public_inputs, 2, 2);
-        // let common_data_1_bytes = common_data1.to_bytes(gate_serializer)
+        info!("proof 1: {:?}, degree bits 1: {}", res_1.0.public_inputs, res_1.2.degree_bits());
+        info!("proof 2: {:?}, degree bits 2: {}", res_2.0.public_inputs, res_2.2.degree_bits());
+
+        // The two circuits share their `CommonCircuitData` but not their verifier data, `aggregate` verifies both.
+        let ((proof, verifier_only, common), _) = aggregate(&[res_1, res_2], &CircuitConfig::standard_recursion_config())?;
+        info!("aggregated: {:?}, degree bits: {}", proof.public_inputs, common.degree_bits());
+        VerifierCircuitData { verifier_only, common }.verify(proof)?;
 
         Ok(())
     }
@@ -1,3 +1,440 @@
-fn main() {
-    println!("Hello, world!");
+use std::cell::RefCell;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::Once;
+
+use anyhow::{anyhow, Context};
+use clap::{Parser, Subcommand};
+use log::{LevelFilter, Log, Metadata, Record};
+
+use plonky2::plonk::circuit_data::CircuitData;
+use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+use plonky2::plonk::proof::ProofWithPublicInputs;
+use plonky2::util::timing::TimingTree;
+
+use plonky2_cook::analysis::diff::{diff_common_data, diff_verifier_only};
+use plonky2_cook::analysis::mock_prover::mock_prove;
+use plonky2_cook::analysis::profile::prove_with_report;
+use plonky2_cook::analysis::stats::circuit_stats;
+use plonky2_cook::examples::{Example, ExampleCircuit};
+use plonky2_cook::serialization::{CookGateSerializer, CookGeneratorSerializer};
+use plonky2_cook::witness::parse_witness_values;
+
+const D: usize = 2;
+type C = PoseidonGoldilocksConfig;
+type F = <C as GenericConfig<D>>::F;
+
+const EXIT_CODES: &str = "\
+Exit codes:
+    0  success
+    1  the proof does not verify, or `diff` found circuits needing different recursive verifiers
+    2  invalid command line
+    3  unreadable or malformed input file, or a bad witness
+    4  the witness does not satisfy the circuit";
+
+#[derive(Parser)]
+#[command(name = "cook", about = "Build, prove and verify the cook example circuits", after_help = EXIT_CODES)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List the example circuits and their inputs
+    List,
+    /// Build an example circuit and write it to a file
+    Build {
+        example: Example,
+        /// The size of the example, see `cook list`
+        #[arg(long)]
+        size: Option<usize>,
+        #[arg(long, short)]
+        out: PathBuf,
+    },
+    /// Prove a circuit file written by `build` from a witness file of `name = value` lines, and write the proof to a file
+    Prove {
+        /// The example the circuit file was built from, it gives the names of the witness file
+        example: Example,
+        #[arg(long)]
+        size: Option<usize>,
+        #[arg(long, short)]
+        circuit: PathBuf,
+        #[arg(long, short)]
+        witness: PathBuf,
+        #[arg(long, short)]
+        out: PathBuf,
+        /// Print the time and memory of every prover phase to stderr
+        #[arg(long)]
+        report: bool,
+        /// Write the prover phase report as JSON to a file
+        #[arg(long)]
+        report_json: Option<PathBuf>,
+    },
+    /// Verify a proof file against a circuit file
+    Verify {
+        #[arg(long, short)]
+        circuit: PathBuf,
+        #[arg(long, short)]
+        proof: PathBuf,
+    },
+    /// Print the statistics of a circuit file
+    Stats {
+        #[arg(long, short)]
+        circuit: PathBuf,
+    },
+    /// Compare the shapes of two circuit files, and tell whether one recursive verifier can verify both
+    Diff {
+        a: PathBuf,
+        b: PathBuf,
+    },
+}
+
+enum Failure {
+    Rejected(anyhow::Error),
+    Input(anyhow::Error),
+    Unsatisfied(anyhow::Error),
+}
+
+impl Failure {
+    fn exit_code(&self) -> u8 {
+        match self {
+            Failure::Rejected(_) => 1,
+            Failure::Input(_) => 3,
+            Failure::Unsatisfied(_) => 4,
+        }
+    }
+
+    fn error(&self) -> &anyhow::Error {
+        match self {
+            Failure::Rejected(e) | Failure::Input(e) | Failure::Unsatisfied(e) => e,
+        }
+    }
+}
+
+fn build(example: Example, size: Option<usize>) -> Result<ExampleCircuit<F, C, D>, Failure> {
+    example
+        .build::<F, C, D>(size.unwrap_or(example.default_size()))
+        .map_err(Failure::Input)
+}
+
+fn read(path: &Path) -> Result<Vec<u8>, Failure> {
+    fs::read(path)
+        .with_context(|| format!("cannot read {}", path.display()))
+        .map_err(Failure::Input)
+}
+
+fn write(path: &Path, bytes: &[u8]) -> Result<(), Failure> {
+    fs::write(path, bytes)
+        .with_context(|| format!("cannot write {}", path.display()))
+        .map_err(Failure::Input)
+}
+
+fn read_circuit(path: &Path) -> Result<CircuitData<F, C, D>, Failure> {
+    CircuitData::<F, C, D>::from_bytes(
+        &read(path)?,
+        &CookGateSerializer,
+        &CookGeneratorSerializer::<C, D>::default(),
+    )
+    .map_err(|_| Failure::Input(anyhow!("{} is not a circuit file", path.display())))
+}
+
+fn run(command: Command) -> Result<(), Failure> {
+    match command {
+        Command::List => {
+            for example in Example::ALL {
+                let circuit = build(example, None)?;
+                let inputs: Vec<&str> = circuit.inputs.iter().map(|(n, _)| n.as_str()).collect();
+                println!(
+                    "{:<18} size {:<3} inputs: {}",
+                    example.name(),
+                    example.default_size(),
+                    inputs.join(" ")
+                );
+            }
+        }
+        Command::Build { example, size, out } => {
+            let circuit = build(example, size)?;
+            let bytes = circuit
+                .data
+                .to_bytes(&CookGateSerializer, &CookGeneratorSerializer::<C, D>::default())
+                .map_err(|_| Failure::Input(anyhow!("CircuitData serialization failed.")))?;
+            write(&out, &bytes)?;
+            print!("{}", circuit_stats(&circuit.data));
+        }
+        Command::Prove {
+            example,
+            size,
+            circuit: circuit_file,
+            witness,
+            out,
+            report: print_report,
+            report_json,
+        } => {
+            // The example is only rebuilt to find the targets of the witness names, the proof is of the file.
+            let mut circuit = build(example, size)?;
+            let data = read_circuit(&circuit_file)?;
+            if data.verifier_only.circuit_digest != circuit.data.verifier_only.circuit_digest {
+                return Err(Failure::Input(anyhow!(
+                    "{} is not a circuit of `{}` of size {}",
+                    circuit_file.display(),
+                    example,
+                    circuit.size
+                )));
+            }
+            circuit.data = data;
+            let text = String::from_utf8(read(&witness)?)
+                .map_err(|_| Failure::Input(anyhow!("{} is not a text file", witness.display())))?;
+            let values = parse_witness_values(&text)
+                .with_context(|| format!("invalid witness file {}", witness.display()))
+                .map_err(Failure::Input)?;
+            let pw = circuit.witness(&values).map_err(Failure::Input)?;
+
+            // The mock prover tells which row and gate fail, `prove` would only panic.
+            let report = mock_prove(&circuit.data, pw.clone()).map_err(Failure::Unsatisfied)?;
+            if !report.is_ok() {
+                return Err(Failure::Unsatisfied(anyhow!("{report}")));
+            }
+
+            let proof = if print_report || report_json.is_some() {
+                let (proof, report) =
+                    prove_with_report(&circuit.data, pw, capture_timing).map_err(Failure::Unsatisfied)?;
+                if print_report {
+                    eprint!("{report}");
+                }
+                if let Some(path) = report_json {
+                    write(&path, report.to_json().as_bytes())?;
+                }
+                proof
+            } else {
+                circuit.data.prove(pw).map_err(Failure::Unsatisfied)?
+            };
+            write(&out, &proof.to_bytes())?;
+            for value in proof.public_inputs.iter() {
+                println!("{value}");
+            }
+        }
+        Command::Verify { circuit, proof } => {
+            let data = read_circuit(&circuit)?;
+            let proof = ProofWithPublicInputs::<F, C, D>::from_bytes(read(&proof)?, &data.common)
+                .with_context(|| format!("{} is not a proof of {}", proof.display(), circuit.display()))
+                .map_err(Failure::Input)?;
+            data.verify(proof).map_err(Failure::Rejected)?;
+            println!("ok");
+        }
+        Command::Stats { circuit } => {
+            print!("{}", circuit_stats(&read_circuit(&circuit)?));
+        }
+        Command::Diff { a, b } => {
+            let (a, b) = (read_circuit(&a)?, read_circuit(&b)?);
+            let diff = diff_common_data(&a.common, &b.common);
+            print!("{diff}");
+            let verifier_diff = diff_verifier_only(&a.verifier_only, &b.verifier_only);
+            if diff.is_identical() && !verifier_diff.is_identical() {
+                print!("{verifier_diff}");
+            }
+            if !diff.is_recursively_compatible() {
+                return Err(Failure::Rejected(anyhow!("the circuits need different recursive verifiers")));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+thread_local! {
+    /// The messages logged on this thread while `Some`.
+    static CAPTURED: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+}
+
+/// `env_logger` configured from `RUST_LOG`, except for the records captured by `capture_timing`.
+struct CookLogger {
+    inner: env_logger::Logger,
+}
+
+impl Log for CookLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        CAPTURED.with(|c| c.borrow().is_some()) || self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        let captured = CAPTURED.with(|c| match c.borrow_mut().as_mut() {
+            Some(lines) => {
+                lines.push(record.args().to_string());
+                true
+            }
+            None => false,
+        });
+        if !captured {
+            self.inner.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+fn init_logger() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        let inner = env_logger::Builder::from_default_env().build();
+        log::set_max_level(inner.filter());
+        log::set_boxed_logger(Box::new(CookLogger { inner })).expect("`cook` installs the only logger");
+    });
+}
+
+/// The lines `TimingTree::print` logs, whatever `RUST_LOG` filters.
+fn capture_timing(timing: &TimingTree) -> Vec<String> {
+    CAPTURED.with(|c| *c.borrow_mut() = Some(Vec::new()));
+    let max_level = log::max_level();
+    log::set_max_level(LevelFilter::Trace);
+    timing.print();
+    log::set_max_level(max_level);
+    CAPTURED.with(|c| c.borrow_mut().take()).unwrap_or_default()
+}
+
+fn main() -> ExitCode {
+    init_logger();
+    let cli = Cli::parse();
+    match run(cli.command) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(failure) => {
+            eprintln!("error: {:#}", failure.error());
+            ExitCode::from(failure.exit_code())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::Value;
+
+    use plonky2::field::types::Field;
+
+    use plonky2_cook::analysis::profile::Phase;
+
+    /// A fresh directory under the system temp dir for the files of one test.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("cook-{name}-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn exit_code(command: Command) -> u8 {
+        match run(command) {
+            Ok(()) => 0,
+            Err(failure) => failure.exit_code(),
+        }
+    }
+
+    /// Builds `example` of `size` into `dir`, and returns the circuit file.
+    fn build_file(dir: &Path, example: Example, size: usize) -> PathBuf {
+        let out = dir.join(format!("{example}-{size}.circuit"));
+        let command = Command::Build {
+            example,
+            size: Some(size),
+            out: out.clone(),
+        };
+        assert_eq!(exit_code(command), 0);
+        out
+    }
+
+    fn prove_array_sum(dir: &Path, circuit: &Path, witness: &str, report_json: Option<PathBuf>) -> (PathBuf, u8) {
+        let witness_file = dir.join("array_sum.witness");
+        let out = dir.join("array_sum.proof");
+        fs::write(&witness_file, witness).unwrap();
+
+        let prove = Command::Prove {
+            example: Example::ArraySum,
+            size: Some(3),
+            circuit: circuit.to_path_buf(),
+            witness: witness_file,
+            out: out.clone(),
+            report: false,
+            report_json,
+        };
+        (out, exit_code(prove))
+    }
+
+    #[test]
+    fn prove_report_json() {
+        init_logger();
+        let dir = temp_dir("report");
+        let circuit = build_file(&dir, Example::ArraySum, 3);
+        let report = dir.join("report.json");
+        let (_, code) = prove_array_sum(&dir, &circuit, "x0 = 1\nx1 = 2\nx2 = 3\n", Some(report.clone()));
+        assert_eq!(code, 0);
+
+        // The only logger of the binary is `CookLogger`, so the timing tree is always captured.
+        let json: Value = serde_json::from_str(&fs::read_to_string(report).unwrap()).unwrap();
+        let phases: Vec<&str> = json["phases"].as_array().unwrap().iter().map(|p| p["phase"].as_str().unwrap()).collect();
+        assert!(phases.contains(&Phase::WitnessGeneration.name()));
+        assert!(phases.contains(&Phase::WireCommitment.name()));
+        assert!(phases.contains(&Phase::Fri.name()));
+    }
+
+    #[test]
+    fn exit_codes() {
+        let dir = temp_dir("exit-codes");
+        let circuit = build_file(&dir, Example::ArraySum, 3);
+
+        // 0: the proof of the circuit file verifies against it.
+        let (proof, code) = prove_array_sum(&dir, &circuit, "x0 = 1\nx1 = 2\nx2 = 3\n", None);
+        assert_eq!(code, 0);
+        let verify = || Command::Verify {
+            circuit: circuit.clone(),
+            proof: proof.clone(),
+        };
+        assert_eq!(exit_code(verify()), 0);
+
+        // 1: a proof with another public input does not verify.
+        let data = read_circuit(&circuit).ok().unwrap();
+        let mut tampered = ProofWithPublicInputs::<F, C, D>::from_bytes(fs::read(&proof).unwrap(), &data.common).unwrap();
+        tampered.public_inputs[0] += F::ONE;
+        fs::write(&proof, tampered.to_bytes()).unwrap();
+        assert_eq!(exit_code(verify()), 1);
+
+        // 2: clap rejects the command line before `run`.
+        let error = Cli::try_parse_from(["cook", "prove", "array-sum"]).err().unwrap();
+        assert_eq!(error.exit_code(), 2);
+
+        // 3: the field order itself is not a field element, it is not reduced to zero.
+        let (_, code) = prove_array_sum(&dir, &circuit, "x0 = 18446744069414584321\nx1 = 2\nx2 = 3\n", None);
+        assert_eq!(code, 3);
+        let (_, code) = prove_array_sum(&dir, &dir.join("missing.circuit"), "x0 = 1\nx1 = 2\nx2 = 3\n", None);
+        assert_eq!(code, 3);
+
+        // 3: the circuit file is not the example the witness names are of.
+        let other = build_file(&dir, Example::MulAdd, 1);
+        let (_, code) = prove_array_sum(&dir, &other, "x0 = 1\nx1 = 2\nx2 = 3\n", None);
+        assert_eq!(code, 3);
+
+        // 3: a power above the quotient degree of the config, plonky2 would panic in `build`.
+        let build = Command::Build {
+            example: Example::Exp,
+            size: Some(9),
+            out: dir.join("exp-9.circuit"),
+        };
+        assert_eq!(exit_code(build), 3);
+    }
+
+    #[test]
+    fn diff_exit_codes() {
+        let dir = temp_dir("diff");
+        let sum_2 = build_file(&dir, Example::ArraySum, 2);
+        let sum_3 = build_file(&dir, Example::ArraySum, 3);
+        let mul_add = build_file(&dir, Example::MulAdd, 2);
+
+        // The array sums only differ in their verifier data, which a recursive verifier takes as a witness.
+        let diff = |a: &PathBuf, b: &PathBuf| Command::Diff {
+            a: a.clone(),
+            b: b.clone(),
+        };
+        assert_eq!(exit_code(diff(&sum_2, &sum_3)), 0);
+        assert_eq!(exit_code(diff(&sum_3, &mul_add)), 1);
+    }
 }
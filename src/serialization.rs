@@ -0,0 +1,230 @@
+use core::marker::PhantomData;
+
+use plonky2::field::extension::Extendable;
+use plonky2::gadgets::arithmetic::EqualityGenerator;
+use plonky2::gadgets::arithmetic_extension::QuotientGeneratorExtension;
+use plonky2::gadgets::range_check::LowHighGenerator;
+use plonky2::gadgets::split_base::BaseSumGenerator;
+use plonky2::gadgets::split_join::{SplitGenerator, WireSplitGenerator};
+use plonky2::gates::arithmetic_base::{ArithmeticBaseGenerator, ArithmeticGate};
+use plonky2::gates::arithmetic_extension::{ArithmeticExtensionGate, ArithmeticExtensionGenerator};
+use plonky2::gates::base_sum::{BaseSplitGenerator, BaseSumGate};
+use plonky2::gates::constant::ConstantGate;
+use plonky2::gates::coset_interpolation::{CosetInterpolationGate, InterpolationGenerator};
+use plonky2::gates::exponentiation::{ExponentiationGate, ExponentiationGenerator};
+use plonky2::gates::lookup::{LookupGate, LookupGenerator};
+use plonky2::gates::lookup_table::{LookupTableGate, LookupTableGenerator};
+use plonky2::gates::multiplication_extension::{MulExtensionGate, MulExtensionGenerator};
+use plonky2::gates::noop::NoopGate;
+use plonky2::gates::poseidon::{PoseidonGate, PoseidonGenerator};
+use plonky2::gates::poseidon_mds::{PoseidonMdsGate, PoseidonMdsGenerator};
+use plonky2::gates::public_input::PublicInputGate;
+use plonky2::gates::random_access::{RandomAccessGate, RandomAccessGenerator};
+use plonky2::gates::reducing::{ReducingGate, ReducingGenerator};
+use plonky2::gates::reducing_extension::{
+    ReducingExtensionGate, ReducingGenerator as ReducingExtensionGenerator,
+};
+use plonky2::hash::hash_types::RichField;
+use plonky2::iop::generator::{
+    ConstantGenerator, CopyGenerator, NonzeroTestGenerator, RandomValueGenerator,
+};
+use plonky2::plonk::config::{AlgebraicHasher, GenericConfig};
+use plonky2::recursion::dummy_circuit::DummyProofGenerator;
+use plonky2::util::serialization::{GateSerializer, WitnessGeneratorSerializer};
+use plonky2::{
+    get_gate_tag_impl, get_generator_tag_impl, impl_gate_serializer, impl_generator_serializer,
+    read_gate_impl, read_generator_impl,
+};
+
+use crate::gates::exp_var_gate::{SimpleExpVarGate, SimpleExpVarGenerator};
+use crate::gates::expression_gate::{ExpressionGate, ExpressionGenerator};
+use crate::gates::g_w_v_v_constant::{SimpleExpConstantGate, SimpleExpConstantGenerator};
+use crate::gates::g_w_v_v_low_degree::{SimpleExpLowDegreeGate, SimpleExpLowDegreeGenerator};
+use crate::gates::gate_with_veriable_vars::{SimpleExpTestGate, SimpleExpTestGenerator};
+use crate::gates::simple_add_ext_gate::{SimpleMulAddExtensionGate, SimpleMulAddExtensionGenerator};
+use crate::gates::simple_add_gate::{SimpleMulAddTestGate, SimpleMulAddTestGenerator};
+
+/// The gates of plonky2's `DefaultGateSerializer` plus the cook gates.
+/// The tags are given by the position in the list below, so new gates must be appended at the end.
+#[derive(Debug, Clone, Default)]
+pub struct CookGateSerializer;
+
+impl<F: RichField + Extendable<D>, const D: usize> GateSerializer<F, D> for CookGateSerializer {
+    impl_gate_serializer! {
+        CookGateSerializer,
+        ArithmeticGate,
+        ArithmeticExtensionGate<D>,
+        BaseSumGate<2>,
+        ConstantGate,
+        CosetInterpolationGate<F, D>,
+        ExponentiationGate<F, D>,
+        LookupGate,
+        LookupTableGate,
+        MulExtensionGate<D>,
+        NoopGate,
+        PoseidonMdsGate<F, D>,
+        PoseidonGate<F, D>,
+        PublicInputGate,
+        RandomAccessGate<F, D>,
+        ReducingExtensionGate<D>,
+        ReducingGate<D>,
+        SimpleMulAddTestGate,
+        SimpleExpTestGate,
+        SimpleExpConstantGate,
+        SimpleExpLowDegreeGate,
+        SimpleExpVarGate,
+        SimpleMulAddExtensionGate<D>,
+        ExpressionGate
+    }
+}
+
+/// The generators of plonky2's `DefaultGeneratorSerializer` plus the generators of the cook gates.
+#[derive(Debug, Clone, Default)]
+pub struct CookGeneratorSerializer<C: GenericConfig<D>, const D: usize> {
+    pub _phantom: PhantomData<C>,
+}
+
+impl<F, C, const D: usize> WitnessGeneratorSerializer<F, D> for CookGeneratorSerializer<C, D>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F> + 'static,
+    C::Hasher: AlgebraicHasher<F>,
+{
+    impl_generator_serializer! {
+        CookGeneratorSerializer,
+        ArithmeticBaseGenerator<F, D>,
+        ArithmeticExtensionGenerator<F, D>,
+        BaseSplitGenerator<2>,
+        BaseSumGenerator<2>,
+        ConstantGenerator<F>,
+        CopyGenerator,
+        DummyProofGenerator<F, C, D>,
+        EqualityGenerator,
+        ExponentiationGenerator<F, D>,
+        InterpolationGenerator<F, D>,
+        LookupGenerator,
+        LookupTableGenerator,
+        LowHighGenerator,
+        MulExtensionGenerator<F, D>,
+        NonzeroTestGenerator,
+        PoseidonGenerator<F, D>,
+        PoseidonMdsGenerator<D>,
+        QuotientGeneratorExtension<D>,
+        RandomAccessGenerator<F, D>,
+        RandomValueGenerator,
+        ReducingGenerator<D>,
+        ReducingExtensionGenerator<D>,
+        SplitGenerator,
+        WireSplitGenerator,
+        SimpleMulAddTestGenerator<F, D>,
+        SimpleExpTestGenerator<F, D>,
+        SimpleExpConstantGenerator<F, D>,
+        SimpleExpLowDegreeGenerator<F, D>,
+        SimpleExpVarGenerator<F, D>,
+        SimpleMulAddExtensionGenerator<F, D>,
+        ExpressionGenerator<F, D>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use anyhow::Result;
+
+    use plonky2::field::types::{Field, Sample};
+    use plonky2::iop::target::Target;
+    use plonky2::iop::witness::{PartialWitness, WitnessWrite};
+    use plonky2::plonk::circuit_builder::CircuitBuilder;
+    use plonky2::plonk::circuit_data::{
+        CircuitConfig, CircuitData, ProverCircuitData, VerifierCircuitData,
+    };
+    use plonky2::plonk::config::PoseidonGoldilocksConfig;
+
+    use crate::builder::CookBuilderExt;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    /// Serializes the prover and verifier data of `data`, reloads them and proves/verifies with the reloaded ones.
+    fn round_trip(data: CircuitData<F, C, D>, pw: PartialWitness<F>) -> Result<()> {
+        let gate_serializer = CookGateSerializer;
+        let generator_serializer = CookGeneratorSerializer::<C, D>::default();
+
+        let verifier_data = VerifierCircuitData {
+            verifier_only: data.verifier_only.clone(),
+            common: data.common.clone(),
+        };
+        let verifier_bytes = verifier_data
+            .to_bytes(&gate_serializer)
+            .map_err(|_| anyhow::Error::msg("VerifierCircuitData serialization failed."))?;
+        let prover_bytes = data
+            .prover_data()
+            .to_bytes(&gate_serializer, &generator_serializer)
+            .map_err(|_| anyhow::Error::msg("ProverCircuitData serialization failed."))?;
+
+        let prover_data = ProverCircuitData::<F, C, D>::from_bytes(
+            &prover_bytes,
+            &gate_serializer,
+            &generator_serializer,
+        )
+        .map_err(|_| anyhow::Error::msg("ProverCircuitData deserialization failed."))?;
+        let verifier_data = VerifierCircuitData::<F, C, D>::from_bytes(verifier_bytes, &gate_serializer)
+            .map_err(|_| anyhow::Error::msg("VerifierCircuitData deserialization failed."))?;
+
+        let proof = prover_data.prove(pw)?;
+        verifier_data.verify(proof)
+    }
+
+    #[test]
+    fn mul_add_gate() -> Result<()> {
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let targets: Vec<Target> = (0..5).map(|_| builder.add_virtual_target()).collect();
+        let output = builder.mul_add3(targets[0], targets[1], targets[2], targets[3], targets[4]);
+        builder.register_public_input(output);
+
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        for &t in targets.iter() {
+            pw.set_target(t, F::rand());
+        }
+
+        round_trip(data, pw)
+    }
+
+    #[test]
+    fn exp_gate() -> Result<()> {
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config.clone());
+
+        let gate = SimpleExpTestGate::new(8, &config);
+        let row = builder.add_gate(gate.clone(), vec![]);
+
+        let data = builder.build::<C>();
+
+        // `SimpleExpTestGenerator` only depends on the base and writes the powers and the output.
+        let mut pw = PartialWitness::new();
+        pw.set_target(Target::wire(row, gate.wire_base()), F::rand());
+
+        round_trip(data, pw)
+    }
+
+    #[test]
+    fn exp_constant_gate() -> Result<()> {
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config.clone());
+
+        let base = F::from_canonical_u32(3);
+        let gate = SimpleExpConstantGate::new(8, &config);
+        builder.add_gate(gate, vec![base]);
+
+        let data = builder.build::<C>();
+
+        // The base is a constant, `SimpleExpConstantGenerator` writes every wire of the row.
+        round_trip(data, PartialWitness::new())
+    }
+}
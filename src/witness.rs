@@ -0,0 +1,316 @@
+use core::fmt;
+
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+
+use plonky2::field::extension::Extendable;
+use plonky2::field::types::{Field, Field64};
+use plonky2::hash::hash_types::RichField;
+use plonky2::iop::target::Target;
+use plonky2::iop::witness::{PartialWitness, WitnessWrite};
+use plonky2::plonk::circuit_data::CircuitData;
+use plonky2::plonk::config::GenericConfig;
+
+use crate::analysis::generator_deps::validate_circuit_generators;
+
+/// Parses a witness file made of `name = value` lines, where `value` is a `u64`.
+/// Blank lines and everything after a `#` are ignored.
+pub fn parse_witness_values(text: &str) -> Result<Vec<(String, u64)>> {
+    let mut values: Vec<(String, u64)> = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let (name, value) = line
+            .split_once('=')
+            .ok_or_else(|| anyhow!("line {}: expected `name = value`", i + 1))?;
+        let (name, value) = (name.trim(), value.trim());
+        if name.is_empty() {
+            return Err(anyhow!("line {}: missing name", i + 1));
+        }
+        let value = value
+            .parse::<u64>()
+            .map_err(|e| anyhow!("line {}: invalid value `{}` for `{}`: {}", i + 1, value, name, e))?;
+
+        if values.iter().any(|(n, _)| n == name) {
+            return Err(anyhow!("line {}: `{}` is set twice", i + 1, name));
+        }
+        values.push((name.to_string(), value));
+    }
+
+    Ok(values)
+}
+
+/// A JSON witness file:
+///
+/// ```json
+/// {
+///     "labels": { "x": "12", "y": "0x1f", "z": 7 },
+///     "public_inputs": { "0": "19" }
+/// }
+/// ```
+///
+/// Labels are the names of `LabelledBuilder::label` or `ExampleCircuit::inputs`, public inputs are their index.
+/// A value is a JSON integer, a decimal string or a `0x` hex string, and must be below the field order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WitnessFile {
+    pub labels: Vec<(String, u128)>,
+    pub public_inputs: Vec<(usize, u128)>,
+}
+
+fn parse_json_value(key: &str, value: &Value) -> Result<u128> {
+    match value {
+        Value::Number(n) => n
+            .as_u64()
+            .map(u128::from)
+            .ok_or_else(|| anyhow!("`{key}`: {n} is not a non-negative integer")),
+        Value::String(s) => match s.strip_prefix("0x") {
+            Some(hex) => u128::from_str_radix(hex, 16),
+            None => s.parse::<u128>(),
+        }
+        .map_err(|e| anyhow!("`{key}`: invalid value `{s}`: {e}")),
+        value => Err(anyhow!("`{key}`: expected a number or a string, found {value}")),
+    }
+}
+
+/// Parses a `WitnessFile`.
+pub fn parse_witness_json(text: &str) -> Result<WitnessFile> {
+    let value: Value = serde_json::from_str(text)?;
+    let object = value.as_object().ok_or_else(|| anyhow!("a witness file is a JSON object"))?;
+
+    let mut file = WitnessFile::default();
+    for (section, entries) in object.iter() {
+        let entries = entries
+            .as_object()
+            .ok_or_else(|| anyhow!("`{section}` must be an object"))?;
+        match section.as_str() {
+            "labels" => {
+                for (name, value) in entries.iter() {
+                    file.labels.push((name.clone(), parse_json_value(name, value)?));
+                }
+            }
+            "public_inputs" => {
+                for (index, value) in entries.iter() {
+                    let i = index
+                        .parse::<usize>()
+                        .map_err(|_| anyhow!("`{index}` is not a public input index"))?;
+                    file.public_inputs.push((i, parse_json_value(index, value)?));
+                }
+            }
+            _ => return Err(anyhow!("unknown section `{section}`, expected `labels` or `public_inputs`")),
+        }
+    }
+
+    Ok(file)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WitnessIssue {
+    /// The file sets a label the circuit does not have.
+    UnknownLabel(String),
+    /// The file sets a public input past the last one.
+    UnknownPublicInput { index: usize, num_public_inputs: usize },
+    /// The value is not below the field order.
+    OutOfRange { key: String, value: u128 },
+    /// A label or public input is neither set by the file nor computed by a generator.
+    Missing(String),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct WitnessReport {
+    pub issues: Vec<WitnessIssue>,
+}
+
+impl WitnessReport {
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+impl fmt::Display for WitnessReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for issue in self.issues.iter() {
+            match issue {
+                WitnessIssue::UnknownLabel(name) => writeln!(f, "unknown label `{name}`")?,
+                WitnessIssue::UnknownPublicInput { index, num_public_inputs } => writeln!(
+                    f,
+                    "public input {index} does not exist, the circuit has {num_public_inputs}"
+                )?,
+                WitnessIssue::OutOfRange { key, value } => {
+                    writeln!(f, "`{key}` = {value} is not below the field order")?
+                }
+                WitnessIssue::Missing(key) => writeln!(f, "`{key}` is not set and no generator computes it")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Fills a `PartialWitness` of `data` from `file`, and reports every entry which is wrong or missing.
+/// `labels` name targets of `data`, e.g. `LabelTable::labels`.
+pub fn witness_from_file<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    file: &WitnessFile,
+    data: &CircuitData<F, C, D>,
+    labels: &[(String, Target)],
+) -> (PartialWitness<F>, WitnessReport) {
+    let mut pw = PartialWitness::new();
+    let mut issues = Vec::new();
+    let mut set_targets = Vec::new();
+    let public_inputs = &data.prover_only.public_inputs;
+
+    let entries = file
+        .labels
+        .iter()
+        .map(|(name, value)| match labels.iter().find(|(n, _)| n == name) {
+            Some((_, target)) => Ok((name.clone(), *target, *value)),
+            None => Err(WitnessIssue::UnknownLabel(name.clone())),
+        })
+        .chain(file.public_inputs.iter().map(|&(index, value)| match public_inputs.get(index) {
+            Some(&target) => Ok((format!("public input {index}"), target, value)),
+            None => Err(WitnessIssue::UnknownPublicInput {
+                index,
+                num_public_inputs: public_inputs.len(),
+            }),
+        }));
+    for entry in entries {
+        match entry {
+            Ok((key, _, value)) if value >= u128::from(F::ORDER) => issues.push(WitnessIssue::OutOfRange { key, value }),
+            Ok((_, target, value)) => {
+                pw.set_target(target, F::from_canonical_u64(value as u64));
+                set_targets.push(target);
+            }
+            Err(issue) => issues.push(issue),
+        }
+    }
+
+    // A target is missing if nothing of its copy-constraint partition is set or generated.
+    let (num_wires, degree) = (data.common.config.num_wires, data.common.degree());
+    let representative = |t: Target| data.prover_only.representative_map[t.index(num_wires, degree)];
+    let generators = validate_circuit_generators(data, &[]);
+    let mut known: Vec<usize> = generators
+        .generators
+        .iter()
+        .flat_map(|g| g.outputs.iter().map(|&t| representative(t)))
+        .chain(set_targets.iter().map(|&t| representative(t)))
+        .collect();
+    known.sort_unstable();
+
+    let keys = labels
+        .iter()
+        .map(|(name, t)| (format!("label {name}"), *t))
+        .chain(public_inputs.iter().enumerate().map(|(i, t)| (format!("public input {i}"), *t)));
+    for (key, target) in keys {
+        if known.binary_search(&representative(target)).is_err() {
+            issues.push(WitnessIssue::Missing(key));
+        }
+    }
+
+    (pw, WitnessReport { issues })
+}
+
+/// `witness_from_file`, failing with the whole report if there is any issue.
+pub fn load_witness<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    file: &WitnessFile,
+    data: &CircuitData<F, C, D>,
+    labels: &[(String, Target)],
+) -> Result<PartialWitness<F>> {
+    let (pw, report) = witness_from_file(file, data, labels);
+    if !report.is_ok() {
+        return Err(anyhow!("{report}"));
+    }
+    Ok(pw)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use plonky2::plonk::circuit_data::CircuitConfig;
+    use plonky2::plonk::config::PoseidonGoldilocksConfig;
+
+    use crate::examples::Example;
+    use crate::labels::LabelledBuilder;
+
+    #[test]
+    fn parse() -> Result<()> {
+        let values = parse_witness_values("# inputs\nx0 = 1\n\n  x1=2 # second\n")?;
+        assert_eq!(values, vec![("x0".to_string(), 1), ("x1".to_string(), 2)]);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(parse_witness_values("x0 1").is_err());
+        assert!(parse_witness_values("= 1").is_err());
+        assert!(parse_witness_values("x0 = -1").is_err());
+        assert!(parse_witness_values("x0 = 1\nx0 = 2").is_err());
+    }
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    #[test]
+    fn parse_json() -> Result<()> {
+        let file = parse_witness_json(r#"{ "labels": { "x": 12, "y": "0x1f", "z": "18446744073709551616" }, "public_inputs": { "0": "3" } }"#)?;
+        assert_eq!(
+            file.labels,
+            vec![("x".to_string(), 12), ("y".to_string(), 31), ("z".to_string(), 1 << 64)]
+        );
+        assert_eq!(file.public_inputs, vec![(0, 3)]);
+
+        assert!(parse_witness_json(r#"{ "labels": { "x": -1 } }"#).is_err());
+        assert!(parse_witness_json(r#"{ "labels": { "x": "0xg" } }"#).is_err());
+        assert!(parse_witness_json(r#"{ "public_inputs": { "a": 1 } }"#).is_err());
+        assert!(parse_witness_json(r#"{ "inputs": {} }"#).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn load_labelled() -> Result<()> {
+        let mut builder = LabelledBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_target();
+        let y = builder.add_virtual_target();
+        let sum = builder.add(x, y);
+        builder.label(x, "x");
+        builder.label(y, "y");
+        builder.label(sum, "sum");
+        builder.register_public_input(sum);
+        let (data, table) = builder.build::<C>();
+
+        // `sum` is computed, so it may be left out.
+        let file = parse_witness_json(r#"{ "labels": { "x": "0x10", "y": 2 } }"#)?;
+        let proof = data.prove(load_witness(&file, &data, &table.labels())?)?;
+        assert_eq!(proof.public_inputs, vec![F::from_canonical_u64(18)]);
+        data.verify(proof)
+    }
+
+    #[test]
+    fn load_errors() -> Result<()> {
+        let circuit = Example::ArraySum.build::<F, C, D>(2)?;
+        let file = parse_witness_json(
+            r#"{ "labels": { "x0": "18446744069414584321", "w": 1 }, "public_inputs": { "0": 1, "1": 2 } }"#,
+        )?;
+        let (_, report) = witness_from_file(&file, &circuit.data, &circuit.inputs);
+
+        assert_eq!(
+            report.issues,
+            vec![
+                WitnessIssue::UnknownLabel("w".to_string()),
+                WitnessIssue::OutOfRange {
+                    key: "x0".to_string(),
+                    value: u128::from(F::ORDER),
+                },
+                WitnessIssue::UnknownPublicInput {
+                    index: 1,
+                    num_public_inputs: 1,
+                },
+                WitnessIssue::Missing("label x0".to_string()),
+                WitnessIssue::Missing("label x1".to_string()),
+            ]
+        );
+        Ok(())
+    }
+}